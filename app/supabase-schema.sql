@@ -4,7 +4,8 @@
 -- Enable UUID extension
 CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
 
--- Agents table
+-- Agents table (cache only; description, avatar, capabilities and portfolio
+-- are published on-chain by update_agent_profile as a metadata URI and hash)
 CREATE TABLE agents (
     id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
     wallet_address TEXT NOT NULL,
@@ -45,7 +46,8 @@ CREATE TABLE jobs (
     created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
 );
 
--- Applications table
+-- Applications table (pitch and listing data only; the on-chain Application
+-- PDA created by apply_to_job is the source of truth for worker consent)
 CREATE TABLE applications (
     id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
     job_id UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
@@ -1,15 +1,41 @@
 use anchor_lang::prelude::*;
 use anchor_lang::system_program;
+use anchor_spl::token_interface::{
+    self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+use solana_sha256_hasher::{hash, hashv};
 
 declare_id!("3guFi1GbjiSKxVvsG5mQhP34vHYWBhUX98TibcoRHKZD");
 
 // Constants
 pub const MAX_NAME_LENGTH: usize = 32;
+pub const MAX_METADATA_URI_LENGTH: usize = 128;
+pub const MAX_RESULT_URI_LENGTH: usize = 128;
 pub const MAX_JOB_ID_LENGTH: usize = 36; // UUID length
+pub const MAX_SERVICE_ID_LENGTH: usize = 32;
 pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
-pub const MAX_SPLIT_BPS: u16 = 5000; // 50% max to creator
-pub const DEFAULT_SPLIT_BPS: u16 = 1000; // 10% default to creator
-pub const VERIFICATION_THRESHOLD: u32 = 3; // Jobs needed for verification
+pub const MAX_SPLIT_BPS: u16 = 5000; // 50% max to creator (initial config)
+pub const DEFAULT_SPLIT_BPS: u16 = 1000; // 10% default to creator (initial config)
+pub const VERIFICATION_THRESHOLD: u32 = 3; // Jobs needed for verification (initial config)
+pub const DEFAULT_TIMEOUTS: [u8; MAX_ALLOWED_TIMEOUTS] = [24, 48, 72, 0]; // initial config
+pub const MAX_ALLOWED_TIMEOUTS: usize = 4;
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 1000; // 10% hard cap on protocol fee
+pub const MAX_REPUTATION_MINTS: usize = 8; // token mints whose jobs can count toward reputation
+pub const DEFAULT_MIN_REPUTATION_JOB_VALUE: u64 = LAMPORTS_PER_SOL / 100; // 0.01 SOL (initial config)
+pub const MAX_MILESTONES: usize = 8;
+pub const MAX_PREREQUISITES: usize = 4;
+pub const MAX_PIPELINE_STAGES: usize = 8;
+pub const MAX_AUTO_HIRE_APPLICANTS: u32 = 10; // applications or sealed bids checked in one transaction
+
+// Session key permissions (bitmask of instructions a session key may sign)
+pub const SESSION_CREATE_JOB: u16 = 1 << 0; // create_job, create_milestone_job, purchase_service, create_subjob
+pub const SESSION_APPLY: u16 = 1 << 1; // apply_to_job, withdraw_application, bids
+pub const SESSION_HIRE: u16 = 1 << 2; // hire_agent
+pub const SESSION_COMPLETE: u16 = 1 << 3; // complete_job, complete_milestone, reveal_key
+pub const SESSION_APPROVE: u16 = 1 << 4; // approve_job, approve_milestone
+pub const SESSION_CANCEL: u16 = 1 << 5; // cancel_job, reclaim_expired
+pub const SESSION_DISPUTE: u16 = 1 << 6; // dispute_job
+pub const SESSION_ALL: u16 = (1 << 7) - 1;
 
 // Job status enum values
 pub const STATUS_OPEN: u8 = 0;
@@ -18,6 +44,25 @@ pub const STATUS_PENDING_APPROVAL: u8 = 2;
 pub const STATUS_COMPLETED: u8 = 3;
 pub const STATUS_DISPUTED: u8 = 4;
 pub const STATUS_CANCELLED: u8 = 5;
+pub const STATUS_RESOLVED: u8 = 6;
+pub const STATUS_AWAITING_KEY: u8 = 7; // approved, payout waits for the worker's key
+
+// Hire modes
+pub const HIRE_MODE_MANUAL: u8 = 0; // requester hires with hire_agent
+pub const HIRE_MODE_FIRST_QUALIFIED: u8 = 1; // auto_hire takes the first qualified applicant
+pub const HIRE_MODE_BEST_AFTER: u8 = 2; // auto_hire takes the best applicant once the window closes
+pub const HIRE_MODE_AUCTION: u8 = 3; // sealed-bid reverse auction, lowest revealed bid wins
+
+// Escrow PDA seed layouts
+pub const ESCROW_SEEDS_LEGACY: u8 = 0; // [b"escrow", job_id], migrated from the original program
+pub const ESCROW_SEEDS_REQUESTER: u8 = 1; // [b"escrow", requester_agent, job_id]
+
+// Milestone status enum values
+pub const MILESTONE_PENDING: u8 = 0;
+pub const MILESTONE_SUBMITTED: u8 = 1;
+pub const MILESTONE_RELEASED: u8 = 2;
+
+pub const BPS_DENOMINATOR: u16 = 10000;
 
 #[program]
 pub mod agentlink {
@@ -45,7 +90,31 @@ pub mod agentlink {
         agent.total_earned = 0;
         agent.total_spent = 0;
         agent.reputation_score = 0;
-        agent.creator_split_bps = DEFAULT_SPLIT_BPS;
+        agent.creator_split_bps = ctx.accounts.config.default_split_bps;
+        agent.no_shows = 0;
+        agent.pending_authority = Pubkey::default();
+        agent.metadata_uri = String::new();
+        agent.metadata_hash = [0; 32];
+        agent.capabilities = 0;
+        agent.jobs_paid = 0;
+        agent.timeouts_suffered = 0;
+        agent.disputes_raised = 0;
+        agent.cancellations = 0;
+        agent.total_approval_latency = 0;
+        agent.requester_score = 0;
+        agent.disputes_lost = 0;
+        agent.last_completed_at = 0;
+        agent.weighted_jobs_bps = 0;
+        agent.weighted_earned = 0;
+
+        emit!(AgentRegistered {
+            agent: agent.key(),
+            creator: agent.creator,
+            authority: agent.authority,
+            name: agent.name.clone(),
+            creator_split_bps: agent.creator_split_bps,
+            timestamp: clock.unix_timestamp,
+        });
 
         msg!("Agent '{}' registered by {}", agent.name, agent.creator);
 
@@ -53,552 +122,7451 @@ pub mod agentlink {
     }
 
     /// Create a new job with escrow
+    #[allow(clippy::too_many_arguments)]
     pub fn create_job(
         ctx: Context<CreateJob>,
         job_id: String,
         job_hash: [u8; 32],
         amount: u64,
         timeout_hours: u8,
+        review_hours: u8,
+        required_bond: u64,
+        gates: HireGates,
     ) -> Result<()> {
         require!(job_id.len() <= MAX_JOB_ID_LENGTH, AgentLinkError::JobIdTooLong);
         require!(amount > 0, AgentLinkError::InvalidAmount);
         require!(
-            timeout_hours == 24 || timeout_hours == 48 || timeout_hours == 72,
+            ctx.accounts.config.is_allowed_timeout(timeout_hours),
             AgentLinkError::InvalidTimeout
         );
+        require!(
+            ctx.accounts.config.is_allowed_timeout(review_hours),
+            AgentLinkError::InvalidReviewWindow
+        );
+        require!(
+            gates.hire_mode <= HIRE_MODE_AUCTION,
+            AgentLinkError::InvalidHireMode
+        );
+        if gates.hire_mode == HIRE_MODE_AUCTION {
+            require!(
+                gates.hire_window_hours > 0 && gates.reveal_window_hours > 0,
+                AgentLinkError::InvalidAuctionWindow
+            );
+        }
 
         let clock = Clock::get()?;
 
-        // Transfer SOL from requester to escrow PDA first
-        system_program::transfer(
-            CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                system_program::Transfer {
-                    from: ctx.accounts.requester.to_account_info(),
-                    to: ctx.accounts.escrow.to_account_info(),
-                },
-            ),
+        // Session keys fund the escrow from the requester agent PDA instead
+        let funded_by_agent = authorize_agent(
+            &ctx.accounts.requester_agent,
+            ctx.accounts.requester.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_CREATE_JOB,
+        )?;
+
+        // Transfer SOL to escrow PDA first
+        fund_escrow(
+            &ctx.accounts.escrow,
+            &ctx.accounts.requester_agent,
+            &ctx.accounts.requester,
+            &mut ctx.accounts.session,
+            &ctx.accounts.system_program,
+            funded_by_agent,
             amount,
         )?;
 
         // Now set escrow fields
         let escrow = &mut ctx.accounts.escrow;
-        escrow.bump = ctx.bumps.escrow;
-        escrow.job_id = job_id.clone();
-        escrow.job_hash = job_hash;
-        escrow.requester = ctx.accounts.requester_agent.key();
-        escrow.worker = Pubkey::default();
-        escrow.amount = amount;
-        escrow.status = STATUS_OPEN;
-        escrow.timeout_hours = timeout_hours;
-        escrow.deadline = 0; // Set when hired
-        escrow.created_at = clock.unix_timestamp;
+        escrow.init(
+            ctx.bumps.escrow,
+            job_id.clone(),
+            job_hash,
+            ctx.accounts.requester_agent.key(),
+            ctx.accounts.requester.key(),
+            amount,
+            timeout_hours,
+            review_hours,
+            clock.unix_timestamp,
+        );
+        escrow.funded_by_agent = funded_by_agent;
+        escrow.required_bond = required_bond;
+        escrow.set_hire_gates(&gates);
 
         // Update requester's total_spent
         let requester_agent = &mut ctx.accounts.requester_agent;
         requester_agent.total_spent = requester_agent.total_spent.checked_add(amount).unwrap();
 
+        emit!(JobCreated {
+            escrow: ctx.accounts.escrow.key(),
+            job_id: job_id.clone(),
+            job_hash,
+            requester: ctx.accounts.escrow.requester,
+            payer: ctx.accounts.escrow.payer,
+            mint: Pubkey::default(),
+            amount,
+            timeout_hours,
+            review_hours,
+            milestone_count: 0,
+            required_bond,
+            hire_mode: gates.hire_mode,
+            timestamp: clock.unix_timestamp,
+        });
+
         msg!("Job '{}' created with {} lamports escrow", job_id, amount);
 
         Ok(())
     }
 
-    /// Hire an agent for a job
-    pub fn hire_agent(ctx: Context<HireAgent>) -> Result<()> {
-        let escrow = &mut ctx.accounts.escrow;
+    /// Create a new job whose escrow is released milestone by milestone
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_milestone_job(
+        ctx: Context<CreateJob>,
+        job_id: String,
+        job_hash: [u8; 32],
+        milestones: Vec<MilestoneInput>,
+        timeout_hours: u8,
+        review_hours: u8,
+        required_bond: u64,
+        gates: HireGates,
+    ) -> Result<()> {
+        require!(job_id.len() <= MAX_JOB_ID_LENGTH, AgentLinkError::JobIdTooLong);
+        require!(
+            !milestones.is_empty() && milestones.len() <= MAX_MILESTONES,
+            AgentLinkError::InvalidMilestoneCount
+        );
+        require!(
+            ctx.accounts.config.is_allowed_timeout(timeout_hours),
+            AgentLinkError::InvalidTimeout
+        );
+        require!(
+            ctx.accounts.config.is_allowed_timeout(review_hours),
+            AgentLinkError::InvalidReviewWindow
+        );
+        require!(
+            gates.hire_mode <= HIRE_MODE_BEST_AFTER,
+            AgentLinkError::InvalidHireMode
+        );
+
+        let mut amount: u64 = 0;
+        for milestone in milestones.iter() {
+            require!(milestone.amount > 0, AgentLinkError::InvalidAmount);
+            amount = amount.checked_add(milestone.amount).unwrap();
+        }
+
         let clock = Clock::get()?;
 
-        require!(escrow.status == STATUS_OPEN, AgentLinkError::InvalidJobStatus);
+        // Session keys fund the escrow from the requester agent PDA instead
+        let funded_by_agent = authorize_agent(
+            &ctx.accounts.requester_agent,
+            ctx.accounts.requester.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_CREATE_JOB,
+        )?;
+
+        // Transfer SOL to escrow PDA first
+        fund_escrow(
+            &ctx.accounts.escrow,
+            &ctx.accounts.requester_agent,
+            &ctx.accounts.requester,
+            &mut ctx.accounts.session,
+            &ctx.accounts.system_program,
+            funded_by_agent,
+            amount,
+        )?;
+
+        // Now set escrow fields
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.init(
+            ctx.bumps.escrow,
+            job_id.clone(),
+            job_hash,
+            ctx.accounts.requester_agent.key(),
+            ctx.accounts.requester.key(),
+            amount,
+            timeout_hours,
+            review_hours,
+            clock.unix_timestamp,
+        );
+        escrow.funded_by_agent = funded_by_agent;
+        escrow.required_bond = required_bond;
+        escrow.set_hire_gates(&gates);
+        escrow.milestones = milestones
+            .iter()
+            .map(|m| Milestone {
+                amount: m.amount,
+                milestone_hash: m.milestone_hash,
+                status: MILESTONE_PENDING,
+                submitted_at: 0,
+            })
+            .collect();
+
+        let milestone_count = escrow.milestones.len() as u8;
+
+        // Update requester's total_spent
+        let requester_agent = &mut ctx.accounts.requester_agent;
+        requester_agent.total_spent = requester_agent.total_spent.checked_add(amount).unwrap();
 
-        escrow.worker = ctx.accounts.worker_agent.key();
-        escrow.status = STATUS_IN_PROGRESS;
-        escrow.deadline = clock.unix_timestamp + (escrow.timeout_hours as i64 * 3600);
+        emit!(JobCreated {
+            escrow: ctx.accounts.escrow.key(),
+            job_id: job_id.clone(),
+            job_hash,
+            requester: ctx.accounts.escrow.requester,
+            payer: ctx.accounts.escrow.payer,
+            mint: Pubkey::default(),
+            amount,
+            timeout_hours,
+            review_hours,
+            milestone_count,
+            required_bond,
+            hire_mode: gates.hire_mode,
+            timestamp: clock.unix_timestamp,
+        });
 
         msg!(
-            "Agent hired for job '{}'. Deadline: {}",
-            escrow.job_id,
-            escrow.deadline
+            "Job '{}' created with {} milestones and {} lamports escrow",
+            job_id,
+            milestone_count,
+            amount
         );
 
         Ok(())
     }
 
-    /// Mark job as complete (worker)
-    pub fn complete_job(ctx: Context<CompleteJob>) -> Result<()> {
+    /// Apply to an open job (worker). The application is the worker's
+    /// on-chain consent to be hired.
+    pub fn apply_to_job(ctx: Context<ApplyToJob>) -> Result<()> {
+        authorize_agent(
+            &ctx.accounts.worker_agent,
+            ctx.accounts.worker.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_APPLY,
+        )?;
+
         let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
 
+        require!(escrow.status == STATUS_OPEN, AgentLinkError::InvalidJobStatus);
         require!(
-            escrow.status == STATUS_IN_PROGRESS,
-            AgentLinkError::InvalidJobStatus
+            escrow.hire_mode != HIRE_MODE_AUCTION,
+            AgentLinkError::AuctionJob
         );
         require!(
-            escrow.worker == ctx.accounts.worker_agent.key(),
-            AgentLinkError::Unauthorized
+            escrow.requester != ctx.accounts.worker_agent.key(),
+            AgentLinkError::InvalidWorker
+        );
+        require!(
+            passes_hire_gates(escrow, &ctx.accounts.worker_agent, clock.unix_timestamp),
+            AgentLinkError::HireGatesNotMet
+        );
+        // auto_hire has to see every application, so their number is capped
+        require!(
+            escrow.hire_mode == HIRE_MODE_MANUAL
+                || escrow.application_count < MAX_AUTO_HIRE_APPLICANTS,
+            AgentLinkError::ApplicantLimitReached
         );
 
-        escrow.status = STATUS_PENDING_APPROVAL;
+        escrow.application_count = escrow.application_count.checked_add(1).unwrap();
 
-        msg!("Job '{}' marked as complete, pending approval", escrow.job_id);
+        let application = &mut ctx.accounts.application;
+        application.bump = ctx.bumps.application;
+        application.escrow = escrow.key();
+        application.escrow_created_at = escrow.created_at;
+        application.agent = ctx.accounts.worker_agent.key();
+        application.applied_at = clock.unix_timestamp;
+        application.bond = escrow.required_bond;
 
-        Ok(())
-    }
+        // Post the job's required bond with the application
+        if application.bond > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.worker.to_account_info(),
+                        to: application.to_account_info(),
+                    },
+                ),
+                application.bond,
+            )?;
+        }
 
-    /// Approve job and release payment (requester)
-    pub fn approve_job(ctx: Context<ApproveJob>) -> Result<()> {
-        require!(
-            ctx.accounts.escrow.status == STATUS_PENDING_APPROVAL,
-            AgentLinkError::InvalidJobStatus
+        emit!(ApplicationSubmitted {
+            application: application.key(),
+            escrow: application.escrow,
+            job_id: escrow.job_id.clone(),
+            agent: application.agent,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Agent '{}' applied to job '{}'",
+            ctx.accounts.worker_agent.name,
+            escrow.job_id
         );
 
-        // Extract values we need before any mutable borrows
-        let escrow_amount = ctx.accounts.escrow.amount;
-        let split_bps = ctx.accounts.worker_agent.creator_split_bps;
+        Ok(())
+    }
 
-        // Calculate split
-        let creator_amount = (escrow_amount as u128)
-            .checked_mul(split_bps as u128)
-            .unwrap()
-            .checked_div(10000)
-            .unwrap() as u64;
-        let worker_amount = escrow_amount.checked_sub(creator_amount).unwrap();
+    /// Withdraw an application and reclaim its rent and bond (worker)
+    pub fn withdraw_application(ctx: Context<WithdrawApplication>) -> Result<()> {
+        authorize_agent(
+            &ctx.accounts.worker_agent,
+            ctx.accounts.worker.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_APPLY,
+        )?;
 
-        // Do lamport transfers first
-        if creator_amount > 0 {
-            **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? -= creator_amount;
-            **ctx.accounts.worker_creator.to_account_info().try_borrow_mut_lamports()? += creator_amount;
+        // Closed escrows, and escrows recreated under the same job ID, no
+        // longer track this application
+        let application = &ctx.accounts.application;
+        let escrow_info = &ctx.accounts.escrow;
+        if escrow_info.owner == &crate::ID {
+            let mut escrow = EscrowAccount::try_deserialize(&mut &escrow_info.try_borrow_data()?[..])?;
+            if application.applies_to(escrow_info.key(), &escrow) {
+                escrow.application_count = escrow.application_count.saturating_sub(1);
+                escrow.try_serialize(&mut &mut escrow_info.try_borrow_mut_data()?[..])?;
+            }
         }
 
-        **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? -= worker_amount;
-        **ctx.accounts.worker_agent.to_account_info().try_borrow_mut_lamports()? += worker_amount;
+        emit!(ApplicationWithdrawn {
+            application: application.key(),
+            escrow: application.escrow,
+            agent: application.agent,
+        });
 
-        // Now update account data
-        let worker_agent = &mut ctx.accounts.worker_agent;
-        worker_agent.successful_jobs = worker_agent.successful_jobs.checked_add(1).unwrap();
-        worker_agent.total_earned = worker_agent.total_earned.checked_add(escrow_amount).unwrap();
+        msg!(
+            "Agent '{}' withdrew application for escrow {}",
+            ctx.accounts.worker_agent.name,
+            application.escrow
+        );
 
-        // Check for auto-verification
-        if worker_agent.successful_jobs >= VERIFICATION_THRESHOLD && !worker_agent.verified {
-            worker_agent.verified = true;
-            msg!("Agent '{}' is now verified!", worker_agent.name);
-        }
+        Ok(())
+    }
 
-        // Update reputation score
-        worker_agent.reputation_score = calculate_reputation(
-            worker_agent.successful_jobs,
-            worker_agent.total_earned,
-        );
+    /// Reject an application to an open job, returning its rent and bond to
+    /// the applicant (requester). Frees a slot under the auto-hire cap.
+    pub fn reject_application(ctx: Context<RejectApplication>) -> Result<()> {
+        authorize_agent(
+            &ctx.accounts.requester_agent,
+            ctx.accounts.requester.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_HIRE,
+        )?;
 
         let escrow = &mut ctx.accounts.escrow;
-        escrow.status = STATUS_COMPLETED;
+        require!(escrow.status == STATUS_OPEN, AgentLinkError::InvalidJobStatus);
+        escrow.application_count = escrow.application_count.saturating_sub(1);
+
+        let application = &ctx.accounts.application;
+
+        emit!(ApplicationRejected {
+            application: application.key(),
+            escrow: application.escrow,
+            agent: application.agent,
+        });
 
         msg!(
-            "Job '{}' approved. Worker received {} lamports, creator received {} lamports",
-            escrow.job_id,
-            worker_amount,
-            creator_amount
+            "Application from agent '{}' to job '{}' rejected",
+            ctx.accounts.worker_agent.name,
+            escrow.job_id
         );
 
         Ok(())
     }
 
-    /// Claim payment after timeout (anyone can call)
-    pub fn claim_timeout(ctx: Context<ClaimTimeout>) -> Result<()> {
+    /// Hire an agent for a job. Requires the worker's consent, either as an
+    /// application or as a co-signature from the worker's authority.
+    pub fn hire_agent(ctx: Context<HireAgent>) -> Result<()> {
+        authorize_agent(
+            &ctx.accounts.requester_agent,
+            ctx.accounts.requester.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_HIRE,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
         let clock = Clock::get()?;
 
+        require!(escrow.status == STATUS_OPEN, AgentLinkError::InvalidJobStatus);
         require!(
-            ctx.accounts.escrow.status == STATUS_PENDING_APPROVAL,
-            AgentLinkError::InvalidJobStatus
+            escrow.hire_mode != HIRE_MODE_AUCTION,
+            AgentLinkError::AuctionJob
         );
         require!(
-            clock.unix_timestamp > ctx.accounts.escrow.deadline,
-            AgentLinkError::DeadlineNotReached
+            escrow.prerequisites_met(),
+            AgentLinkError::PrerequisitesIncomplete
         );
 
-        // Extract values we need
-        let escrow_amount = ctx.accounts.escrow.amount;
-        let split_bps = ctx.accounts.worker_agent.creator_split_bps;
+        let worker_agent_key = ctx.accounts.worker_agent.key();
+        let applied = match &ctx.accounts.application {
+            Some(application) => {
+                application.applies_to(escrow.key(), escrow) && application.agent == worker_agent_key
+            }
+            None => false,
+        };
+        let cosigned = match &ctx.accounts.worker {
+            Some(worker) => worker.key() == ctx.accounts.worker_agent.authority,
+            None => false,
+        };
+        require!(applied || cosigned, AgentLinkError::WorkerConsentRequired);
 
-        // Calculate split
-        let creator_amount = (escrow_amount as u128)
-            .checked_mul(split_bps as u128)
-            .unwrap()
-            .checked_div(10000)
-            .unwrap() as u64;
-        let worker_amount = escrow_amount.checked_sub(creator_amount).unwrap();
+        // Refuse self-hiring between agents that share a creator or authority
+        let requester_agent = &ctx.accounts.requester_agent;
+        let worker_agent = &ctx.accounts.worker_agent;
+        require!(
+            requester_agent.creator != worker_agent.creator
+                && requester_agent.authority != worker_agent.authority,
+            AgentLinkError::SelfDealing
+        );
+        require!(
+            passes_hire_gates(escrow, worker_agent, clock.unix_timestamp),
+            AgentLinkError::HireGatesNotMet
+        );
 
-        // Do lamport transfers first
-        if creator_amount > 0 {
-            **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? -= creator_amount;
-            **ctx.accounts.worker_creator.to_account_info().try_borrow_mut_lamports()? += creator_amount;
+        // Move the worker's bond into the escrow, from the application or
+        // from the co-signing worker
+        let bond = escrow.required_bond;
+        if bond > 0 {
+            if applied {
+                let application = ctx.accounts.application.as_mut().unwrap();
+                take_application_bond(application, escrow, bond)?;
+            } else {
+                let worker = ctx.accounts.worker.as_ref().unwrap();
+                system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: worker.to_account_info(),
+                            to: escrow.to_account_info(),
+                        },
+                    ),
+                    bond,
+                )?;
+                escrow.bond = bond;
+            }
         }
 
-        **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? -= worker_amount;
-        **ctx.accounts.worker_agent.to_account_info().try_borrow_mut_lamports()? += worker_amount;
+        start_job(
+            escrow,
+            &mut ctx.accounts.pair,
+            ctx.bumps.pair,
+            worker_agent_key,
+            clock.unix_timestamp,
+        )
+    }
 
-        // Now update account data
-        let worker_agent = &mut ctx.accounts.worker_agent;
-        worker_agent.successful_jobs = worker_agent.successful_jobs.checked_add(1).unwrap();
-        worker_agent.total_earned = worker_agent.total_earned.checked_add(escrow_amount).unwrap();
+    /// Hire an applicant under the job's hire mode (anyone can call). Every
+    /// application for the job must be passed as (application, worker_agent)
+    /// pairs in `remaining_accounts`, sorted by application key.
+    /// `first_qualified` hires the earliest qualified applicant.
+    /// `best_after` waits for the hire window to close and hires the
+    /// highest-reputation qualified applicant (earliest on ties).
+    pub fn auto_hire<'info>(ctx: Context<'_, '_, 'info, 'info, AutoHire<'info>>) -> Result<()> {
+        let clock = Clock::get()?;
+        let escrow = &ctx.accounts.escrow;
+
+        require!(escrow.status == STATUS_OPEN, AgentLinkError::InvalidJobStatus);
+        require!(
+            escrow.hire_mode == HIRE_MODE_FIRST_QUALIFIED
+                || escrow.hire_mode == HIRE_MODE_BEST_AFTER,
+            AgentLinkError::AutoHireDisabled
+        );
+        require!(
+            escrow.prerequisites_met(),
+            AgentLinkError::PrerequisitesIncomplete
+        );
+        require!(
+            is_qualified_applicant(
+                escrow,
+                &ctx.accounts.requester_agent,
+                &ctx.accounts.application,
+                &ctx.accounts.worker_agent,
+                clock.unix_timestamp,
+            ),
+            AgentLinkError::HireGatesNotMet
+        );
 
-        // Check for auto-verification
-        if worker_agent.successful_jobs >= VERIFICATION_THRESHOLD && !worker_agent.verified {
-            worker_agent.verified = true;
-            msg!("Agent '{}' is now verified!", worker_agent.name);
+        let best_after = escrow.hire_mode == HIRE_MODE_BEST_AFTER;
+        if best_after {
+            require!(
+                clock.unix_timestamp >= escrow.hire_after,
+                AgentLinkError::HireWindowOpen
+            );
         }
 
-        // Update reputation score
-        worker_agent.reputation_score = calculate_reputation(
-            worker_agent.successful_jobs,
-            worker_agent.total_earned,
+        let applicants = ctx.remaining_accounts;
+        require!(
+            applicants.len() == escrow.application_count as usize * 2,
+            AgentLinkError::IncompleteApplicantSet
         );
 
-        let escrow = &mut ctx.accounts.escrow;
-        let job_id = escrow.job_id.clone();
-        escrow.status = STATUS_COMPLETED;
+        // Keys must strictly increase so no application is counted twice
+        let mut previous = Pubkey::default();
+        let mut best: Option<(u16, i64, Pubkey)> = None;
+        for applicant in applicants.chunks(2) {
+            let application = Account::<Application>::try_from(&applicant[0])?;
+            let worker_agent = Account::<AgentAccount>::try_from(&applicant[1])?;
+            require!(
+                application.key() > previous
+                    && application.applies_to(escrow.key(), escrow)
+                    && application.agent == worker_agent.key(),
+                AgentLinkError::IncompleteApplicantSet
+            );
+            previous = application.key();
 
-        msg!(
-            "Job '{}' auto-released after timeout. Worker received {} lamports",
-            job_id,
-            worker_amount
+            if !is_qualified_applicant(
+                escrow,
+                &ctx.accounts.requester_agent,
+                &application,
+                &worker_agent,
+                clock.unix_timestamp,
+            ) {
+                continue;
+            }
+
+            // first_qualified ranks every qualified applicant equally
+            let score = if best_after {
+                worker_agent.reputation_at(clock.unix_timestamp)
+            } else {
+                0
+            };
+            let better = match best {
+                Some((best_score, best_applied_at, _)) => {
+                    score > best_score
+                        || (score == best_score && application.applied_at < best_applied_at)
+                }
+                None => true,
+            };
+            if better {
+                best = Some((score, application.applied_at, application.key()));
+            }
+        }
+
+        require!(
+            best.map(|(_, _, key)| key) == Some(ctx.accounts.application.key()),
+            AgentLinkError::NotBestApplicant
         );
 
-        Ok(())
+        let bond = ctx.accounts.escrow.required_bond;
+        if bond > 0 {
+            take_application_bond(&mut ctx.accounts.application, &mut ctx.accounts.escrow, bond)?;
+        }
+
+        let worker_agent_key = ctx.accounts.worker_agent.key();
+        start_job(
+            &mut ctx.accounts.escrow,
+            &mut ctx.accounts.pair,
+            ctx.bumps.pair,
+            worker_agent_key,
+            clock.unix_timestamp,
+        )
     }
 
-    /// Cancel job and refund (requester, only if status is Open)
-    pub fn cancel_job(ctx: Context<CancelJob>) -> Result<()> {
-        require!(ctx.accounts.escrow.status == STATUS_OPEN, AgentLinkError::InvalidJobStatus);
+    /// Commit a sealed bid on an auction job (worker) as sha256 of the bid
+    /// amount (little-endian u64) followed by a 32-byte salt. Posts the job's
+    /// required bond with the bid, which is forfeited to the requester if the
+    /// bid is never revealed.
+    pub fn commit_bid(ctx: Context<CommitBid>, commitment: [u8; 32]) -> Result<()> {
+        authorize_agent(
+            &ctx.accounts.worker_agent,
+            ctx.accounts.worker.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_APPLY,
+        )?;
 
-        // Get refund amount
-        let refund_amount = ctx.accounts.escrow.amount;
+        let escrow = &ctx.accounts.escrow;
+        let clock = Clock::get()?;
 
-        // Do lamport transfer first
-        **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? -= refund_amount;
-        **ctx.accounts.requester.to_account_info().try_borrow_mut_lamports()? += refund_amount;
+        require!(
+            escrow.hire_mode == HIRE_MODE_AUCTION,
+            AgentLinkError::NotAuctionJob
+        );
+        require!(escrow.status == STATUS_OPEN, AgentLinkError::InvalidJobStatus);
+        require!(
+            clock.unix_timestamp < escrow.hire_after,
+            AgentLinkError::BiddingClosed
+        );
 
-        // Now update account data
-        let requester_agent = &mut ctx.accounts.requester_agent;
-        requester_agent.total_spent = requester_agent.total_spent.checked_sub(refund_amount).unwrap();
+        let requester_agent = &ctx.accounts.requester_agent;
+        let worker_agent = &ctx.accounts.worker_agent;
+        require!(
+            requester_agent.creator != worker_agent.creator
+                && requester_agent.authority != worker_agent.authority,
+            AgentLinkError::SelfDealing
+        );
+        require!(
+            passes_hire_gates(escrow, worker_agent, clock.unix_timestamp),
+            AgentLinkError::HireGatesNotMet
+        );
+        // settle_auction has to see every unrevealed bid, so their number is capped
+        require!(
+            escrow.application_count < MAX_AUTO_HIRE_APPLICANTS,
+            AgentLinkError::ApplicantLimitReached
+        );
 
         let escrow = &mut ctx.accounts.escrow;
-        let job_id = escrow.job_id.clone();
-        escrow.status = STATUS_CANCELLED;
-        escrow.amount = 0;
+        escrow.application_count = escrow.application_count.checked_add(1).unwrap();
 
-        msg!("Job '{}' cancelled, {} lamports refunded", job_id, refund_amount);
+        let bid = &mut ctx.accounts.bid;
+        bid.bump = ctx.bumps.bid;
+        bid.escrow = escrow.key();
+        bid.escrow_created_at = escrow.created_at;
+        bid.agent = worker_agent.key();
+        bid.commitment = commitment;
+        bid.amount = 0;
+        bid.revealed = false;
+        bid.committed_at = clock.unix_timestamp;
+        bid.bond = escrow.required_bond;
+
+        // Post the job's required bond with the bid
+        if bid.bond > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.worker.to_account_info(),
+                        to: bid.to_account_info(),
+                    },
+                ),
+                bid.bond,
+            )?;
+        }
+
+        emit!(BidCommitted {
+            bid: bid.key(),
+            escrow: bid.escrow,
+            job_id: escrow.job_id.clone(),
+            agent: bid.agent,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Agent '{}' committed a bid on job '{}'",
+            worker_agent.name,
+            escrow.job_id
+        );
 
         Ok(())
     }
 
-    /// Dispute a job (either party)
-    pub fn dispute_job(ctx: Context<DisputeJob>) -> Result<()> {
+    /// Reveal a committed bid during the reveal window (worker). The lowest
+    /// revealed bid leads; the earlier reveal keeps the lead on ties.
+    pub fn reveal_bid(ctx: Context<RevealBid>, amount: u64, salt: [u8; 32]) -> Result<()> {
+        authorize_agent(
+            &ctx.accounts.worker_agent,
+            ctx.accounts.worker.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_APPLY,
+        )?;
+
         let escrow = &mut ctx.accounts.escrow;
+        let bid = &mut ctx.accounts.bid;
+        let clock = Clock::get()?;
 
         require!(
-            escrow.status == STATUS_IN_PROGRESS || escrow.status == STATUS_PENDING_APPROVAL,
-            AgentLinkError::InvalidJobStatus
+            escrow.hire_mode == HIRE_MODE_AUCTION,
+            AgentLinkError::NotAuctionJob
         );
-
-        // Verify caller is either requester or worker
-        let caller = ctx.accounts.caller.key();
-        let caller_agent = ctx.accounts.caller_agent.key();
+        require!(escrow.status == STATUS_OPEN, AgentLinkError::InvalidJobStatus);
         require!(
-            caller_agent == escrow.requester || caller_agent == escrow.worker,
-            AgentLinkError::Unauthorized
+            clock.unix_timestamp >= escrow.hire_after
+                && clock.unix_timestamp < escrow.reveal_deadline,
+            AgentLinkError::RevealNotOpen
+        );
+        require!(!bid.revealed, AgentLinkError::BidAlreadyRevealed);
+        require!(
+            hashv(&[&amount.to_le_bytes(), &salt]).to_bytes() == bid.commitment,
+            AgentLinkError::BidMismatch
+        );
+        require!(
+            amount > 0 && amount <= escrow.amount,
+            AgentLinkError::InvalidBid
+        );
+        require!(
+            passes_hire_gates(escrow, &ctx.accounts.worker_agent, clock.unix_timestamp),
+            AgentLinkError::HireGatesNotMet
         );
 
-        escrow.status = STATUS_DISPUTED;
+        bid.amount = amount;
+        bid.revealed = true;
+        escrow.application_count = escrow.application_count.checked_sub(1).unwrap();
 
-        msg!("Job '{}' disputed by {}", escrow.job_id, caller);
+        if escrow.best_bidder == Pubkey::default() || amount < escrow.best_bid {
+            escrow.best_bid = amount;
+            escrow.best_bidder = bid.agent;
+        }
+
+        emit!(BidRevealed {
+            bid: bid.key(),
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            agent: bid.agent,
+            amount,
+            best_bid: escrow.best_bid,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Bid of {} lamports revealed on job '{}', best bid is {}",
+            amount,
+            escrow.job_id,
+            escrow.best_bid
+        );
 
         Ok(())
     }
 
-    /// Configure creator split percentage
-    pub fn configure_split(ctx: Context<ConfigureSplit>, new_split_bps: u16) -> Result<()> {
-        require!(new_split_bps <= MAX_SPLIT_BPS, AgentLinkError::SplitTooHigh);
+    /// Close the auction after the reveal window (anyone can call). Hires the
+    /// lowest bidder, funds the escrow at its bid and refunds the excess to
+    /// the requester. Every unrevealed bid must be passed in
+    /// `remaining_accounts`, sorted by key; their bonds go to the requester.
+    pub fn settle_auction<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleAuction<'info>>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let escrow = &ctx.accounts.escrow;
 
-        let agent = &mut ctx.accounts.agent;
-        agent.creator_split_bps = new_split_bps;
+        require!(
+            escrow.hire_mode == HIRE_MODE_AUCTION,
+            AgentLinkError::NotAuctionJob
+        );
+        require!(escrow.status == STATUS_OPEN, AgentLinkError::InvalidJobStatus);
+        require!(
+            clock.unix_timestamp >= escrow.reveal_deadline,
+            AgentLinkError::AuctionInProgress
+        );
+        require!(
+            escrow.best_bidder != Pubkey::default(),
+            AgentLinkError::NoValidBids
+        );
+        require!(
+            escrow.prerequisites_met(),
+            AgentLinkError::PrerequisitesIncomplete
+        );
 
-        msg!("Agent '{}' split updated to {}bps", agent.name, new_split_bps);
+        // Forfeit the bonds of bids that were never revealed
+        let forfeited = forfeit_unrevealed_bids(
+            &ctx.accounts.escrow,
+            &ctx.accounts.requester_agent,
+            ctx.remaining_accounts,
+        )?;
 
-        Ok(())
-    }
+        // Return the difference between the escrowed budget and the winning bid
+        let winning_bid = escrow.best_bid;
+        let refund = escrow.amount.checked_sub(winning_bid).unwrap();
+        if refund > 0 {
+            refund_requester(
+                &ctx.accounts.escrow,
+                &ctx.accounts.requester_agent,
+                &ctx.accounts.requester,
+                false,
+                refund,
+            )?;
+            let requester_agent = &mut ctx.accounts.requester_agent;
+            requester_agent.total_spent = requester_agent.total_spent.checked_sub(refund).unwrap();
+        }
 
-    /// Withdraw earnings from agent PDA
-    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
-        // Get agent PDA balance (excluding rent)
-        let rent = Rent::get()?;
-        let min_balance = rent.minimum_balance(AgentAccount::SPACE);
-        let agent_balance = ctx.accounts.agent.to_account_info().lamports();
-        let available = agent_balance.checked_sub(min_balance).unwrap_or(0);
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.amount = winning_bid;
+        escrow.application_count = 0;
 
-        let withdraw_amount = if amount == 0 { available } else { amount };
-        require!(withdraw_amount <= available, AgentLinkError::InsufficientFunds);
-        require!(withdraw_amount > 0, AgentLinkError::NothingToWithdraw);
+        // Move the winner's bond from its bid into the escrow
+        let bond = escrow.required_bond;
+        if bond > 0 {
+            let bid = &mut ctx.accounts.bid;
+            require!(bid.bond >= bond, AgentLinkError::BondNotPosted);
+            bid.bond = bid.bond.checked_sub(bond).unwrap();
+            **bid.to_account_info().try_borrow_mut_lamports()? -= bond;
+            **escrow.to_account_info().try_borrow_mut_lamports()? += bond;
+            escrow.bond = bond;
+        }
 
-        // Transfer from agent PDA to authority
-        **ctx.accounts.agent.to_account_info().try_borrow_mut_lamports()? -= withdraw_amount;
-        **ctx.accounts.authority.to_account_info().try_borrow_mut_lamports()? += withdraw_amount;
+        emit!(AuctionSettled {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            worker: escrow.best_bidder,
+            winning_bid,
+            refund,
+            forfeited,
+            timestamp: clock.unix_timestamp,
+        });
 
-        msg!(
-            "Withdrew {} lamports from agent '{}' to {}",
-            withdraw_amount,
-            ctx.accounts.agent.name,
-            ctx.accounts.authority.key()
+        let worker_agent_key = ctx.accounts.worker_agent.key();
+        start_job(
+            escrow,
+            &mut ctx.accounts.pair,
+            ctx.bumps.pair,
+            worker_agent_key,
+            clock.unix_timestamp,
+        )
+    }
+
+    /// Close an auction that ended without a valid revealed bid (anyone can
+    /// call). Every unrevealed bid must be passed in `remaining_accounts`,
+    /// sorted by key; their bonds go to the requester, who can then cancel
+    /// the job.
+    pub fn close_failed_auction<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseFailedAuction<'info>>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let escrow = &ctx.accounts.escrow;
+
+        require!(
+            escrow.hire_mode == HIRE_MODE_AUCTION,
+            AgentLinkError::NotAuctionJob
+        );
+        require!(escrow.status == STATUS_OPEN, AgentLinkError::InvalidJobStatus);
+        require!(
+            clock.unix_timestamp >= escrow.reveal_deadline,
+            AgentLinkError::AuctionInProgress
+        );
+        require!(
+            escrow.best_bidder == Pubkey::default(),
+            AgentLinkError::AuctionHasWinner
+        );
+
+        let forfeited = forfeit_unrevealed_bids(
+            &ctx.accounts.escrow,
+            &ctx.accounts.requester_agent,
+            ctx.remaining_accounts,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.application_count = 0;
+
+        emit!(AuctionFailed {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            forfeited,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Auction for job '{}' ended without a valid bid, {} lamports of bonds forfeited",
+            escrow.job_id,
+            forfeited
+        );
+
+        Ok(())
+    }
+
+    /// Withdraw a bid and reclaim its rent and bond (worker). Bids stay locked
+    /// while the auction is running, while they hold the lead and, if never
+    /// revealed, until settlement forfeits their bond.
+    pub fn withdraw_bid(ctx: Context<WithdrawBid>) -> Result<()> {
+        authorize_agent(
+            &ctx.accounts.worker_agent,
+            ctx.accounts.worker.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_APPLY,
+        )?;
+
+        // Closed escrows, and escrows recreated under the same job ID, no
+        // longer hold this bid
+        let escrow_info = &ctx.accounts.escrow;
+        if escrow_info.owner == &crate::ID {
+            let escrow = EscrowAccount::try_deserialize(&mut &escrow_info.try_borrow_data()?[..])?;
+            let bid = &ctx.accounts.bid;
+            if escrow.status == STATUS_OPEN && bid.bids_on(escrow_info.key(), &escrow) {
+                require!(
+                    Clock::get()?.unix_timestamp >= escrow.reveal_deadline
+                        && bid.revealed
+                        && escrow.best_bidder != bid.agent,
+                    AgentLinkError::AuctionInProgress
+                );
+            }
+        }
+
+        let bid = &ctx.accounts.bid;
+
+        emit!(BidWithdrawn {
+            bid: bid.key(),
+            escrow: bid.escrow,
+            agent: bid.agent,
+        });
+
+        msg!(
+            "Agent '{}' withdrew bid for escrow {}",
+            ctx.accounts.worker_agent.name,
+            bid.escrow
+        );
+
+        Ok(())
+    }
+
+    /// Publish a fixed-price service that requesters can buy instantly (worker)
+    pub fn create_service_listing(
+        ctx: Context<CreateServiceListing>,
+        service_id: String,
+        terms: ServiceTerms,
+    ) -> Result<()> {
+        require!(
+            service_id.len() <= MAX_SERVICE_ID_LENGTH,
+            AgentLinkError::ServiceIdTooLong
+        );
+        validate_service_terms(&terms, &ctx.accounts.agent, &ctx.accounts.config)?;
+
+        let clock = Clock::get()?;
+        let listing = &mut ctx.accounts.listing;
+        listing.bump = ctx.bumps.listing;
+        listing.agent = ctx.accounts.agent.key();
+        listing.service_id = service_id;
+        listing.queue_length = 0;
+        listing.active = true;
+        listing.total_sales = 0;
+        listing.created_at = clock.unix_timestamp;
+        listing.set_terms(&terms);
+
+        emit!(ServiceListed {
+            listing: listing.key(),
+            agent: listing.agent,
+            service_id: listing.service_id.clone(),
+            price: listing.price,
+            capability: listing.capability,
+            max_queue: listing.max_queue,
+            active: listing.active,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Agent '{}' listed service '{}' at {} lamports",
+            ctx.accounts.agent.name,
+            listing.service_id,
+            listing.price
+        );
+
+        Ok(())
+    }
+
+    /// Change a service listing's terms or pause it (worker). Jobs already
+    /// purchased keep the terms they were bought under.
+    pub fn update_service_listing(
+        ctx: Context<UpdateServiceListing>,
+        terms: ServiceTerms,
+        active: bool,
+    ) -> Result<()> {
+        validate_service_terms(&terms, &ctx.accounts.agent, &ctx.accounts.config)?;
+
+        let listing = &mut ctx.accounts.listing;
+        listing.set_terms(&terms);
+        listing.active = active;
+
+        emit!(ServiceListed {
+            listing: listing.key(),
+            agent: listing.agent,
+            service_id: listing.service_id.clone(),
+            price: listing.price,
+            capability: listing.capability,
+            max_queue: listing.max_queue,
+            active,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Service '{}' updated: {} lamports, active: {}",
+            listing.service_id,
+            listing.price,
+            active
+        );
+
+        Ok(())
+    }
+
+    /// Buy a listed service (requester). Creates and funds an escrow that is
+    /// already in progress with the listing's agent. `max_price` guards
+    /// against the price changing before the purchase lands.
+    pub fn purchase_service(
+        ctx: Context<PurchaseService>,
+        job_id: String,
+        max_price: u64,
+    ) -> Result<()> {
+        require!(job_id.len() <= MAX_JOB_ID_LENGTH, AgentLinkError::JobIdTooLong);
+
+        let listing = &ctx.accounts.listing;
+        require!(listing.active, AgentLinkError::ServiceInactive);
+        require!(listing.price <= max_price, AgentLinkError::PriceAboveLimit);
+        require!(
+            listing.queue_length < listing.max_queue,
+            AgentLinkError::ServiceQueueFull
+        );
+
+        // Refuse self-purchases between agents that share a creator or authority
+        let requester_agent = &ctx.accounts.requester_agent;
+        let worker_agent = &ctx.accounts.worker_agent;
+        require!(
+            requester_agent.creator != worker_agent.creator
+                && requester_agent.authority != worker_agent.authority,
+            AgentLinkError::SelfDealing
+        );
+
+        let clock = Clock::get()?;
+        let amount = listing.price;
+
+        // Session keys fund the escrow from the requester agent PDA instead
+        let funded_by_agent = authorize_agent(
+            &ctx.accounts.requester_agent,
+            ctx.accounts.requester.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_CREATE_JOB,
+        )?;
+
+        // Transfer SOL to escrow PDA first
+        fund_escrow(
+            &ctx.accounts.escrow,
+            &ctx.accounts.requester_agent,
+            &ctx.accounts.requester,
+            &mut ctx.accounts.session,
+            &ctx.accounts.system_program,
+            funded_by_agent,
+            amount,
+        )?;
+
+        // Now set escrow fields
+        let listing = &mut ctx.accounts.listing;
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.init(
+            ctx.bumps.escrow,
+            job_id.clone(),
+            listing.terms_hash,
+            ctx.accounts.requester_agent.key(),
+            ctx.accounts.requester.key(),
+            amount,
+            listing.timeout_hours,
+            listing.review_hours,
+            clock.unix_timestamp,
+        );
+        escrow.funded_by_agent = funded_by_agent;
+        escrow.service = listing.key();
+
+        listing.queue_length = listing.queue_length.checked_add(1).unwrap();
+        listing.total_sales = listing.total_sales.checked_add(1).unwrap();
+
+        // Update requester's total_spent
+        let requester_agent = &mut ctx.accounts.requester_agent;
+        requester_agent.total_spent = requester_agent.total_spent.checked_add(amount).unwrap();
+
+        emit!(JobCreated {
+            escrow: escrow.key(),
+            job_id: job_id.clone(),
+            job_hash: escrow.job_hash,
+            requester: escrow.requester,
+            payer: escrow.payer,
+            mint: Pubkey::default(),
+            amount,
+            timeout_hours: escrow.timeout_hours,
+            review_hours: escrow.review_hours,
+            milestone_count: 0,
+            required_bond: 0,
+            hire_mode: HIRE_MODE_MANUAL,
+            timestamp: clock.unix_timestamp,
+        });
+
+        emit!(ServicePurchased {
+            listing: listing.key(),
+            escrow: escrow.key(),
+            job_id,
+            requester: escrow.requester,
+            worker: listing.agent,
+            price: amount,
+            queue_length: listing.queue_length,
+            timestamp: clock.unix_timestamp,
+        });
+
+        let worker_agent_key = ctx.accounts.worker_agent.key();
+        start_job(
+            escrow,
+            &mut ctx.accounts.pair,
+            ctx.bumps.pair,
+            worker_agent_key,
+            clock.unix_timestamp,
+        )
+    }
+
+    /// Free the queue slot held by a purchased job once it is settled or
+    /// reopened (anyone can call)
+    pub fn release_service_slot(ctx: Context<ReleaseServiceSlot>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        require!(
+            escrow.is_terminal() || escrow.status == STATUS_OPEN,
+            AgentLinkError::ServiceJobActive
+        );
+
+        let listing = &mut ctx.accounts.listing;
+        listing.queue_length = listing.queue_length.saturating_sub(1);
+        escrow.service = Pubkey::default();
+
+        emit!(ServiceSlotReleased {
+            listing: listing.key(),
+            escrow: escrow.key(),
+            queue_length: listing.queue_length,
+        });
+
+        msg!(
+            "Service '{}' slot released by job '{}'",
+            listing.service_id,
+            escrow.job_id
+        );
+
+        Ok(())
+    }
+
+    /// Close a service listing and reclaim its rent (worker). Every purchased
+    /// job must have released its slot first.
+    pub fn close_service(ctx: Context<CloseService>) -> Result<()> {
+        let listing = &ctx.accounts.listing;
+        require!(listing.queue_length == 0, AgentLinkError::ServiceQueueNotEmpty);
+
+        emit!(ServiceClosed {
+            listing: listing.key(),
+            agent: listing.agent,
+            service_id: listing.service_id.clone(),
+            total_sales: listing.total_sales,
+        });
+
+        msg!(
+            "Service '{}' closed after {} sales",
+            listing.service_id,
+            listing.total_sales
+        );
+
+        Ok(())
+    }
+
+    /// Carve part of an in-progress job's escrow into a sub-job that the
+    /// hired worker posts for another agent (worker, co-signed by the parent
+    /// requester since the sub-job can pay out before the parent is
+    /// approved). The carved amount comes out of the worker's payout;
+    /// whatever the sub-job refunds goes back to the parent escrow when it
+    /// settles. The sub-job's delivery deadline never runs past the parent's.
+    pub fn create_subjob(
+        ctx: Context<CreateSubjob>,
+        job_id: String,
+        job_hash: [u8; 32],
+        amount: u64,
+        timeout_hours: u8,
+        review_hours: u8,
+    ) -> Result<()> {
+        authorize_agent(
+            &ctx.accounts.worker_agent,
+            ctx.accounts.worker.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_CREATE_JOB,
+        )?;
+
+        let parent = &ctx.accounts.parent_escrow;
+        require!(job_id.len() <= MAX_JOB_ID_LENGTH, AgentLinkError::JobIdTooLong);
+        require!(
+            parent.status == STATUS_IN_PROGRESS,
+            AgentLinkError::InvalidJobStatus
+        );
+        require!(parent.milestones.is_empty(), AgentLinkError::MilestoneJob);
+        require!(
+            Clock::get()?.unix_timestamp <= parent.deadline,
+            AgentLinkError::DeadlinePassed
+        );
+        require!(
+            amount > 0 && amount < parent.amount,
+            AgentLinkError::InvalidAmount
         );
+        require!(
+            ctx.accounts.config.is_allowed_timeout(timeout_hours),
+            AgentLinkError::InvalidTimeout
+        );
+        require!(
+            ctx.accounts.config.is_allowed_timeout(review_hours),
+            AgentLinkError::InvalidReviewWindow
+        );
+
+        let clock = Clock::get()?;
+
+        // Move the sub-job budget from the parent escrow
+        **ctx.accounts.parent_escrow.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        let parent = &mut ctx.accounts.parent_escrow;
+        parent.amount = parent.amount.checked_sub(amount).unwrap();
+        parent.subjob_total = parent.subjob_total.checked_add(amount).unwrap();
+        parent.open_subjobs = parent.open_subjobs.checked_add(1).unwrap();
+
+        // Now set escrow fields
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.init(
+            ctx.bumps.escrow,
+            job_id.clone(),
+            job_hash,
+            ctx.accounts.worker_agent.key(),
+            ctx.accounts.worker.key(),
+            amount,
+            timeout_hours,
+            review_hours,
+            clock.unix_timestamp,
+        );
+        escrow.parent = parent.key();
+        escrow.parent_open = true;
+        escrow.deadline_cap = parent.deadline;
+
+        // The worker is the sub-job's requester
+        let worker_agent = &mut ctx.accounts.worker_agent;
+        worker_agent.total_spent = worker_agent.total_spent.checked_add(amount).unwrap();
+
+        emit!(JobCreated {
+            escrow: escrow.key(),
+            job_id: job_id.clone(),
+            job_hash,
+            requester: escrow.requester,
+            payer: escrow.payer,
+            mint: Pubkey::default(),
+            amount,
+            timeout_hours,
+            review_hours,
+            milestone_count: 0,
+            required_bond: 0,
+            hire_mode: HIRE_MODE_MANUAL,
+            timestamp: clock.unix_timestamp,
+        });
+
+        emit!(SubjobCreated {
+            parent: parent.key(),
+            escrow: escrow.key(),
+            job_id: job_id.clone(),
+            amount,
+            open_subjobs: parent.open_subjobs,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Sub-job '{}' carved {} lamports from job '{}'",
+            job_id,
+            amount,
+            parent.job_id
+        );
+
+        Ok(())
+    }
+
+    /// Release a settled sub-job from its parent's open count and return
+    /// the budget it did not pay out to the parent escrow (anyone can call).
+    /// A sub-job that was never hired is cancelled here once the parent's
+    /// delivery deadline passes, and a hired one once its own deadline
+    /// passes (recording the no-show and slashing the bond to the sub-job's
+    /// requester), so an absent worker on either side cannot hold the
+    /// parent open.
+    pub fn settle_subjob(ctx: Context<SettleSubjob>) -> Result<()> {
+        let clock = Clock::get()?;
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.parent_open, AgentLinkError::SubjobAlreadySettled);
+
+        let unhired = escrow.status == STATUS_OPEN
+            && clock.unix_timestamp > ctx.accounts.parent_escrow.deadline;
+        let expired =
+            escrow.status == STATUS_IN_PROGRESS && clock.unix_timestamp > escrow.deadline;
+        require!(
+            escrow.is_terminal() || unhired || expired,
+            AgentLinkError::InvalidJobStatus
+        );
+
+        if expired {
+            // Record the no-show against the sub-job's worker
+            let worker_key = escrow.worker;
+            let worker_agent = ctx
+                .accounts
+                .worker_agent
+                .as_mut()
+                .ok_or(AgentLinkError::InvalidWorker)?;
+            require!(
+                worker_agent.key() == worker_key,
+                AgentLinkError::InvalidWorker
+            );
+            worker_agent.no_shows = worker_agent.no_shows.checked_add(1).unwrap();
+            update_reputation(worker_agent, clock.unix_timestamp);
+
+            // Slash the worker's bond to the sub-job's requester
+            settle_bond(
+                &mut ctx.accounts.escrow,
+                &ctx.accounts.requester_agent.to_account_info(),
+                true,
+            )?;
+        }
+
+        // Everything above rent is budget the sub-job refunded
+        let escrow_info = ctx.accounts.escrow.to_account_info();
+        let rent = Rent::get()?.minimum_balance(escrow_info.data_len());
+        let refund = escrow_info.lamports().saturating_sub(rent);
+        if refund > 0 {
+            **escrow_info.try_borrow_mut_lamports()? -= refund;
+            **ctx.accounts.parent_escrow.to_account_info().try_borrow_mut_lamports()? += refund;
+        }
+
+        if unhired || expired {
+            let escrow_amount = ctx.accounts.escrow.amount;
+            let requester_agent = &mut ctx.accounts.requester_agent;
+            requester_agent.total_spent = requester_agent.total_spent.checked_sub(escrow_amount).unwrap();
+
+            let escrow = &mut ctx.accounts.escrow;
+            escrow.status = STATUS_CANCELLED;
+            escrow.amount = 0;
+
+            if expired {
+                emit!(JobExpired {
+                    escrow: escrow.key(),
+                    job_id: escrow.job_id.clone(),
+                    requester: escrow.requester,
+                    worker: escrow.worker,
+                    reopened: false,
+                    refund_amount: refund,
+                    timestamp: clock.unix_timestamp,
+                });
+            } else {
+                emit!(JobCancelled {
+                    escrow: escrow.key(),
+                    job_id: escrow.job_id.clone(),
+                    requester: escrow.requester,
+                    mint: escrow.mint,
+                    refund_amount: refund,
+                    timestamp: clock.unix_timestamp,
+                });
+            }
+        }
+
+        let parent = &mut ctx.accounts.parent_escrow;
+        parent.amount = parent.amount.checked_add(refund).unwrap();
+        parent.subjob_total = parent.subjob_total.saturating_sub(refund);
+        parent.open_subjobs = parent.open_subjobs.checked_sub(1).unwrap();
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.parent_open = false;
+
+        emit!(SubjobSettled {
+            parent: parent.key(),
+            escrow: escrow.key(),
+            status: escrow.status,
+            refund,
+            open_subjobs: parent.open_subjobs,
+        });
+
+        msg!(
+            "Sub-job '{}' settled, {} lamports returned, {} still open under job '{}'",
+            escrow.job_id,
+            refund,
+            parent.open_subjobs,
+            parent.job_id
+        );
+
+        Ok(())
+    }
+
+    /// Declare the escrows that must be completed before this job can be
+    /// hired (requester). Replaces any earlier list.
+    pub fn set_prerequisites(
+        ctx: Context<SetPrerequisites>,
+        prerequisites: Vec<Pubkey>,
+    ) -> Result<()> {
+        authorize_agent(
+            &ctx.accounts.requester_agent,
+            ctx.accounts.requester.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_CREATE_JOB,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.status == STATUS_OPEN, AgentLinkError::InvalidJobStatus);
+        require!(
+            prerequisites.len() <= MAX_PREREQUISITES,
+            AgentLinkError::TooManyPrerequisites
+        );
+        for (i, prerequisite) in prerequisites.iter().enumerate() {
+            require!(
+                *prerequisite != escrow.key() && !prerequisites[..i].contains(prerequisite),
+                AgentLinkError::InvalidPrerequisite
+            );
+        }
+
+        escrow.prerequisites = prerequisites;
+        escrow.met_prerequisites = 0;
+
+        emit!(PrerequisitesSet {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            prerequisites: escrow.prerequisites.clone(),
+        });
+
+        msg!(
+            "Job '{}' now waits on {} prerequisites",
+            escrow.job_id,
+            escrow.prerequisites.len()
+        );
+
+        Ok(())
+    }
+
+    /// Record that a prerequisite escrow has been completed (anyone can call).
+    /// Must be recorded before the prerequisite escrow is closed.
+    pub fn record_prerequisite(ctx: Context<RecordPrerequisite>) -> Result<()> {
+        let prerequisite = &ctx.accounts.prerequisite;
+        require!(
+            prerequisite.status == STATUS_COMPLETED,
+            AgentLinkError::PrerequisitesIncomplete
+        );
+
+        let escrow = &mut ctx.accounts.escrow;
+        let index = escrow
+            .prerequisites
+            .iter()
+            .position(|key| *key == prerequisite.key())
+            .ok_or(AgentLinkError::InvalidPrerequisite)?;
+        escrow.met_prerequisites |= 1 << index;
+
+        emit!(PrerequisiteMet {
+            escrow: escrow.key(),
+            prerequisite: prerequisite.key(),
+            all_met: escrow.prerequisites_met(),
+        });
+
+        msg!(
+            "Prerequisite '{}' met for job '{}'",
+            prerequisite.job_id,
+            escrow.job_id
+        );
+
+        Ok(())
+    }
+
+    /// Fund a multi-stage pipeline up front (requester). Stages are launched
+    /// as escrows one at a time, each depending on the previous stage.
+    pub fn create_pipeline(
+        ctx: Context<CreatePipeline>,
+        pipeline_id: String,
+        stages: Vec<PipelineStageInput>,
+    ) -> Result<()> {
+        require!(
+            pipeline_id.len() <= MAX_JOB_ID_LENGTH,
+            AgentLinkError::JobIdTooLong
+        );
+        require!(
+            !stages.is_empty() && stages.len() <= MAX_PIPELINE_STAGES,
+            AgentLinkError::InvalidPipelineStages
+        );
+
+        let mut total: u64 = 0;
+        for stage in stages.iter() {
+            require!(stage.amount > 0, AgentLinkError::InvalidAmount);
+            total = total.checked_add(stage.amount).unwrap();
+        }
+
+        // Transfer the whole chain's budget to the pipeline PDA
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.requester.to_account_info(),
+                    to: ctx.accounts.pipeline.to_account_info(),
+                },
+            ),
+            total,
+        )?;
+
+        let clock = Clock::get()?;
+        let pipeline = &mut ctx.accounts.pipeline;
+        pipeline.bump = ctx.bumps.pipeline;
+        pipeline.requester = ctx.accounts.requester_agent.key();
+        pipeline.pipeline_id = pipeline_id;
+        pipeline.total = total;
+        pipeline.launched = 0;
+        pipeline.created_at = clock.unix_timestamp;
+        pipeline.stages = stages
+            .iter()
+            .map(|stage| PipelineStage {
+                amount: stage.amount,
+                job_hash: stage.job_hash,
+                escrow: Pubkey::default(),
+            })
+            .collect();
+
+        emit!(PipelineCreated {
+            pipeline: pipeline.key(),
+            requester: pipeline.requester,
+            pipeline_id: pipeline.pipeline_id.clone(),
+            stage_count: pipeline.stages.len() as u8,
+            total,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Pipeline '{}' funded with {} lamports across {} stages",
+            pipeline.pipeline_id,
+            total,
+            pipeline.stages.len()
+        );
+
+        Ok(())
+    }
+
+    /// Launch the next pipeline stage as an open escrow funded from the
+    /// pipeline (requester). Every stage after the first depends on the
+    /// escrow of the stage before it.
+    pub fn launch_stage(
+        ctx: Context<LaunchStage>,
+        index: u8,
+        job_id: String,
+        timeout_hours: u8,
+        review_hours: u8,
+    ) -> Result<()> {
+        require!(job_id.len() <= MAX_JOB_ID_LENGTH, AgentLinkError::JobIdTooLong);
+        require!(
+            ctx.accounts.config.is_allowed_timeout(timeout_hours),
+            AgentLinkError::InvalidTimeout
+        );
+        require!(
+            ctx.accounts.config.is_allowed_timeout(review_hours),
+            AgentLinkError::InvalidReviewWindow
+        );
+
+        let pipeline = &ctx.accounts.pipeline;
+        let index = index as usize;
+        let stage = pipeline
+            .stages
+            .get(index)
+            .ok_or(AgentLinkError::InvalidPipelineStage)?;
+        require!(
+            stage.escrow == Pubkey::default(),
+            AgentLinkError::InvalidPipelineStage
+        );
+        let prerequisites = if index == 0 {
+            Vec::new()
+        } else {
+            let previous = pipeline.stages[index - 1].escrow;
+            require!(
+                previous != Pubkey::default(),
+                AgentLinkError::InvalidPipelineStage
+            );
+            vec![previous]
+        };
+        let amount = stage.amount;
+        let job_hash = stage.job_hash;
+
+        // Move the stage budget from the pipeline to the escrow
+        **ctx.accounts.pipeline.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.escrow.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        let clock = Clock::get()?;
+        let pipeline = &mut ctx.accounts.pipeline;
+        pipeline.stages[index].escrow = ctx.accounts.escrow.key();
+        pipeline.launched = pipeline.launched.checked_add(amount).unwrap();
+
+        // Now set escrow fields
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.init(
+            ctx.bumps.escrow,
+            job_id.clone(),
+            job_hash,
+            ctx.accounts.requester_agent.key(),
+            ctx.accounts.requester.key(),
+            amount,
+            timeout_hours,
+            review_hours,
+            clock.unix_timestamp,
+        );
+        escrow.prerequisites = prerequisites;
+        escrow.pipeline = pipeline.key();
+
+        // Update requester's total_spent
+        let requester_agent = &mut ctx.accounts.requester_agent;
+        requester_agent.total_spent = requester_agent.total_spent.checked_add(amount).unwrap();
+
+        emit!(JobCreated {
+            escrow: escrow.key(),
+            job_id: job_id.clone(),
+            job_hash,
+            requester: escrow.requester,
+            payer: escrow.payer,
+            mint: Pubkey::default(),
+            amount,
+            timeout_hours,
+            review_hours,
+            milestone_count: 0,
+            required_bond: 0,
+            hire_mode: HIRE_MODE_MANUAL,
+            timestamp: clock.unix_timestamp,
+        });
+
+        emit!(PipelineStageLaunched {
+            pipeline: pipeline.key(),
+            index: index as u8,
+            escrow: escrow.key(),
+            job_id: job_id.clone(),
+            amount,
+        });
+
+        msg!(
+            "Pipeline '{}' stage {} launched as job '{}'",
+            pipeline.pipeline_id,
+            index,
+            job_id
+        );
+
+        Ok(())
+    }
+
+    /// Cancel a pipeline and refund every stage not yet launched (requester).
+    /// Launched stages that are still open (never hired) can be passed in
+    /// `remaining_accounts` to be cancelled and refunded as well.
+    pub fn cancel_pipeline<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelPipeline<'info>>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let pipeline = &ctx.accounts.pipeline;
+        let mut refund = pipeline.total.checked_sub(pipeline.launched).unwrap();
+        let stages_launched = pipeline
+            .stages
+            .iter()
+            .filter(|stage| stage.escrow != Pubkey::default())
+            .count() as u8;
+
+        // Cancel the open stage escrows passed in
+        let mut stages_cancelled: u8 = 0;
+        for info in ctx.remaining_accounts.iter() {
+            let mut escrow = Account::<EscrowAccount>::try_from(info)?;
+            require!(
+                escrow.pipeline == pipeline.key()
+                    && pipeline.stages.iter().any(|stage| stage.escrow == escrow.key()),
+                AgentLinkError::InvalidPipelineStage
+            );
+            require!(escrow.status == STATUS_OPEN, AgentLinkError::InvalidJobStatus);
+
+            let amount = escrow.amount;
+            refund_requester(
+                &escrow,
+                &ctx.accounts.requester_agent,
+                &ctx.accounts.requester,
+                false,
+                amount,
+            )?;
+            escrow.status = STATUS_CANCELLED;
+            escrow.amount = 0;
+            escrow.exit(&crate::ID)?;
+
+            let requester_agent = &mut ctx.accounts.requester_agent;
+            requester_agent.total_spent = requester_agent.total_spent.checked_sub(amount).unwrap();
+            requester_agent.cancellations = requester_agent.cancellations.checked_add(1).unwrap();
+            refund = refund.checked_add(amount).unwrap();
+            stages_cancelled = stages_cancelled.checked_add(1).unwrap();
+
+            emit!(JobCancelled {
+                escrow: escrow.key(),
+                job_id: escrow.job_id.clone(),
+                requester: escrow.requester,
+                mint: escrow.mint,
+                refund_amount: amount,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+        if stages_cancelled > 0 {
+            refresh_requester_score(&mut ctx.accounts.requester_agent);
+        }
+
+        // Closing the pipeline returns the unlaunched budget with its rent
+        let pipeline = &ctx.accounts.pipeline;
+        emit!(PipelineCancelled {
+            pipeline: pipeline.key(),
+            pipeline_id: pipeline.pipeline_id.clone(),
+            refund,
+            stages_launched,
+            stages_cancelled,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Pipeline '{}' cancelled after {} stages ({} of them cancelled), refunded {} lamports",
+            pipeline.pipeline_id,
+            stages_launched,
+            stages_cancelled,
+            refund
+        );
+
+        Ok(())
+    }
+
+    /// Mark job as complete (worker, before the deadline), committing to the delivered result.
+    /// Starts the requester's review window. Passing a key commitment
+    /// (sha256 of the decryption key) opts into fair-exchange settlement.
+    pub fn complete_job(
+        ctx: Context<CompleteJob>,
+        result_hash: [u8; 32],
+        result_uri: Option<String>,
+        key_commitment: Option<[u8; 32]>,
+    ) -> Result<()> {
+        authorize_agent(
+            &ctx.accounts.worker_agent,
+            ctx.accounts.worker.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_COMPLETE,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        require!(
+            escrow.status == STATUS_IN_PROGRESS,
+            AgentLinkError::InvalidJobStatus
+        );
+        require!(
+            escrow.worker == ctx.accounts.worker_agent.key(),
+            AgentLinkError::Unauthorized
+        );
+        // Past the deadline the requester may reclaim the job as a no-show
+        require!(
+            clock.unix_timestamp <= escrow.deadline,
+            AgentLinkError::DeadlinePassed
+        );
+        require!(escrow.milestones.is_empty(), AgentLinkError::MilestoneJob);
+        require!(escrow.open_subjobs == 0, AgentLinkError::SubjobsOpen);
+
+        let result_uri = result_uri.unwrap_or_default();
+        require!(
+            result_uri.len() <= MAX_RESULT_URI_LENGTH,
+            AgentLinkError::ResultUriTooLong
+        );
+
+        escrow.status = STATUS_PENDING_APPROVAL;
+        escrow.completed_at = clock.unix_timestamp;
+        escrow.result_hash = result_hash;
+        escrow.result_uri = result_uri;
+
+        if let Some(key_commitment) = key_commitment {
+            require!(escrow.is_native(), AgentLinkError::TokenEscrow);
+            require!(
+                key_commitment != [0u8; 32],
+                AgentLinkError::InvalidKeyCommitment
+            );
+            escrow.key_commitment = key_commitment;
+        }
+
+        emit!(JobCompleted {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            worker: escrow.worker,
+            completed_at: escrow.completed_at,
+            review_deadline: escrow.review_deadline(),
+            result_hash: escrow.result_hash,
+            result_uri: escrow.result_uri.clone(),
+            key_commitment: escrow.key_commitment,
+        });
+
+        msg!(
+            "Job '{}' marked as complete, review window ends {}",
+            escrow.job_id,
+            escrow.review_deadline()
+        );
+
+        Ok(())
+    }
+
+    /// Approve job and release payment (requester)
+    pub fn approve_job(ctx: Context<ApproveJob>) -> Result<()> {
+        let used_session = authorize_agent(
+            &ctx.accounts.requester_agent,
+            ctx.accounts.requester.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_APPROVE,
+        )?;
+
+        require!(
+            ctx.accounts.escrow.status == STATUS_PENDING_APPROVAL,
+            AgentLinkError::InvalidJobStatus
+        );
+        if used_session {
+            let amount = ctx.accounts.escrow.amount;
+            charge_session_payout(&mut ctx.accounts.session, &ctx.accounts.escrow, amount)?;
+        }
+
+        // Fair exchange: lock in the approval and wait for the worker's key
+        if ctx.accounts.escrow.has_key_commitment() && !ctx.accounts.escrow.key_revealed {
+            let escrow = &mut ctx.accounts.escrow;
+            escrow.status = STATUS_AWAITING_KEY;
+            escrow.approved_at = Clock::get()?.unix_timestamp;
+
+            emit!(KeyRequested {
+                escrow: escrow.key(),
+                job_id: escrow.job_id.clone(),
+                requester: escrow.requester,
+                worker: escrow.worker,
+                approved_at: escrow.approved_at,
+                reveal_deadline: escrow.key_reveal_deadline(),
+            });
+
+            msg!(
+                "Job '{}' approved, payout released when the worker reveals its key",
+                escrow.job_id
+            );
+
+            return Ok(());
+        }
+
+        // Extract values we need before any mutable borrows
+        let escrow_amount = ctx.accounts.escrow.amount;
+
+        // Do lamport transfers first
+        let (protocol_fee, creator_amount, worker_amount) = payout_worker(
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.worker_agent,
+            &ctx.accounts.worker_creator.to_account_info(),
+            Some(&ctx.accounts.treasury.to_account_info()),
+            escrow_amount,
+            ctx.accounts.config.protocol_fee_bps,
+        )?;
+        let payout = escrow_amount.checked_sub(protocol_fee).unwrap();
+
+        // Now update account data
+        let weight_bps = record_pair_job(
+            &mut ctx.accounts.pair,
+            escrow_amount,
+            &ctx.accounts.escrow.mint,
+            &ctx.accounts.config,
+        );
+        let verification_threshold = ctx.accounts.config.verification_threshold;
+        record_successful_job(
+            &mut ctx.accounts.worker_agent,
+            payout,
+            weight_bps,
+            verification_threshold,
+        )?;
+
+        // Return the worker's bond
+        settle_bond(
+            &mut ctx.accounts.escrow,
+            &ctx.accounts.worker_agent.to_account_info(),
+            false,
+        )?;
+
+        let completed_at = ctx.accounts.escrow.completed_at;
+        record_requester_payment(
+            &mut ctx.accounts.requester_agent,
+            completed_at,
+            Clock::get()?.unix_timestamp,
+            false,
+        );
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.status = STATUS_COMPLETED;
+
+        emit!(JobApproved {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            requester: escrow.requester,
+            worker: escrow.worker,
+            worker_creator: ctx.accounts.worker_creator.key(),
+            mint: escrow.mint,
+            result_hash: escrow.result_hash,
+            amount: escrow_amount,
+            protocol_fee,
+            worker_amount,
+            creator_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Job '{}' approved. Worker received {} lamports, creator received {} lamports",
+            escrow.job_id,
+            worker_amount,
+            creator_amount
+        );
+
+        Ok(())
+    }
+
+    /// Claim payment after the review window lapses (anyone can call)
+    pub fn claim_timeout(ctx: Context<ClaimTimeout>) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(
+            ctx.accounts.escrow.status == STATUS_PENDING_APPROVAL,
+            AgentLinkError::InvalidJobStatus
+        );
+        require!(
+            clock.unix_timestamp > ctx.accounts.escrow.review_deadline(),
+            AgentLinkError::DeadlineNotReached
+        );
+        require!(
+            !ctx.accounts.escrow.has_key_commitment() || ctx.accounts.escrow.key_revealed,
+            AgentLinkError::KeyNotRevealed
+        );
+
+        // Extract values we need
+        let escrow_amount = ctx.accounts.escrow.amount;
+
+        // Do lamport transfers first
+        let (protocol_fee, creator_amount, worker_amount) = payout_worker(
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.worker_agent,
+            &ctx.accounts.worker_creator.to_account_info(),
+            Some(&ctx.accounts.treasury.to_account_info()),
+            escrow_amount,
+            ctx.accounts.config.protocol_fee_bps,
+        )?;
+        let payout = escrow_amount.checked_sub(protocol_fee).unwrap();
+
+        // Now update account data
+        let weight_bps = record_pair_job(
+            &mut ctx.accounts.pair,
+            escrow_amount,
+            &ctx.accounts.escrow.mint,
+            &ctx.accounts.config,
+        );
+        let verification_threshold = ctx.accounts.config.verification_threshold;
+        record_successful_job(
+            &mut ctx.accounts.worker_agent,
+            payout,
+            weight_bps,
+            verification_threshold,
+        )?;
+
+        // Return the worker's bond
+        settle_bond(
+            &mut ctx.accounts.escrow,
+            &ctx.accounts.worker_agent.to_account_info(),
+            false,
+        )?;
+
+        let completed_at = ctx.accounts.escrow.completed_at;
+        record_requester_payment(
+            &mut ctx.accounts.requester_agent,
+            completed_at,
+            clock.unix_timestamp,
+            true,
+        );
+
+        let escrow = &mut ctx.accounts.escrow;
+        let job_id = escrow.job_id.clone();
+        escrow.status = STATUS_COMPLETED;
+
+        emit!(TimeoutClaimed {
+            escrow: escrow.key(),
+            job_id: job_id.clone(),
+            requester: escrow.requester,
+            worker: escrow.worker,
+            worker_creator: ctx.accounts.worker_creator.key(),
+            caller: ctx.accounts.caller.key(),
+            mint: escrow.mint,
+            result_hash: escrow.result_hash,
+            amount: escrow_amount,
+            protocol_fee,
+            worker_amount,
+            creator_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Job '{}' auto-released after timeout. Worker received {} lamports",
+            job_id,
+            worker_amount
+        );
+
+        Ok(())
+    }
+
+    /// Reveal the decryption key committed at completion (worker). Releases the
+    /// payout if the requester already approved; after the review window it
+    /// records the key so `claim_timeout` can release in the same transaction.
+    pub fn reveal_key(ctx: Context<RevealKey>, decryption_key: [u8; 32]) -> Result<()> {
+        authorize_agent(
+            &ctx.accounts.worker_agent,
+            ctx.accounts.worker.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_COMPLETE,
+        )?;
+
+        let clock = Clock::get()?;
+        let escrow = &ctx.accounts.escrow;
+
+        require!(escrow.has_key_commitment(), AgentLinkError::InvalidKeyCommitment);
+        require!(!escrow.key_revealed, AgentLinkError::KeyAlreadyRevealed);
+        require!(
+            hash(&decryption_key).to_bytes() == escrow.key_commitment,
+            AgentLinkError::KeyMismatch
+        );
+
+        let approved = escrow.status == STATUS_AWAITING_KEY;
+        require!(
+            approved
+                || (escrow.status == STATUS_PENDING_APPROVAL
+                    && clock.unix_timestamp > escrow.review_deadline()),
+            AgentLinkError::InvalidJobStatus
+        );
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.decryption_key = decryption_key;
+        escrow.key_revealed = true;
+
+        emit!(KeyRevealed {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            worker: escrow.worker,
+            decryption_key,
+            timestamp: clock.unix_timestamp,
+        });
+
+        if !approved {
+            msg!("Key revealed for job '{}', payout claimable", escrow.job_id);
+            return Ok(());
+        }
+
+        // Extract values we need
+        let escrow_amount = ctx.accounts.escrow.amount;
+
+        // Do lamport transfers first
+        let (protocol_fee, creator_amount, worker_amount) = payout_worker(
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.worker_agent,
+            &ctx.accounts.worker_creator.to_account_info(),
+            Some(&ctx.accounts.treasury.to_account_info()),
+            escrow_amount,
+            ctx.accounts.config.protocol_fee_bps,
+        )?;
+        let payout = escrow_amount.checked_sub(protocol_fee).unwrap();
+
+        // Now update account data
+        let weight_bps = record_pair_job(
+            &mut ctx.accounts.pair,
+            escrow_amount,
+            &ctx.accounts.escrow.mint,
+            &ctx.accounts.config,
+        );
+        let verification_threshold = ctx.accounts.config.verification_threshold;
+        record_successful_job(
+            &mut ctx.accounts.worker_agent,
+            payout,
+            weight_bps,
+            verification_threshold,
+        )?;
+
+        // Return the worker's bond
+        settle_bond(
+            &mut ctx.accounts.escrow,
+            &ctx.accounts.worker_agent.to_account_info(),
+            false,
+        )?;
+
+        // Approval latency counts up to the requester's approval, not the reveal
+        let completed_at = ctx.accounts.escrow.completed_at;
+        let approved_at = ctx.accounts.escrow.approved_at;
+        record_requester_payment(
+            &mut ctx.accounts.requester_agent,
+            completed_at,
+            approved_at,
+            false,
+        );
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.status = STATUS_COMPLETED;
+
+        emit!(JobApproved {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            requester: escrow.requester,
+            worker: escrow.worker,
+            worker_creator: ctx.accounts.worker_creator.key(),
+            mint: escrow.mint,
+            result_hash: escrow.result_hash,
+            amount: escrow_amount,
+            protocol_fee,
+            worker_amount,
+            creator_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Key revealed for job '{}'. Worker received {} lamports, creator received {} lamports",
+            escrow.job_id,
+            worker_amount,
+            creator_amount
+        );
+
+        Ok(())
+    }
+
+    /// Submit a milestone for approval (worker, before the deadline)
+    pub fn complete_milestone(ctx: Context<CompleteMilestone>, index: u8) -> Result<()> {
+        authorize_agent(
+            &ctx.accounts.worker_agent,
+            ctx.accounts.worker.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_COMPLETE,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        require!(
+            escrow.status == STATUS_IN_PROGRESS,
+            AgentLinkError::InvalidJobStatus
+        );
+        require!(
+            escrow.worker == ctx.accounts.worker_agent.key(),
+            AgentLinkError::Unauthorized
+        );
+        // Past the deadline the requester may reclaim the job as a no-show
+        require!(
+            clock.unix_timestamp <= escrow.deadline,
+            AgentLinkError::DeadlinePassed
+        );
+
+        let milestone = escrow
+            .milestones
+            .get_mut(index as usize)
+            .ok_or(AgentLinkError::InvalidMilestone)?;
+        require!(
+            milestone.status == MILESTONE_PENDING,
+            AgentLinkError::InvalidMilestoneStatus
+        );
+
+        milestone.status = MILESTONE_SUBMITTED;
+        milestone.submitted_at = clock.unix_timestamp;
+
+        emit!(MilestoneSubmitted {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            worker: escrow.worker,
+            index,
+            submitted_at: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Milestone {} of job '{}' submitted, pending approval",
+            index,
+            escrow.job_id
+        );
+
+        Ok(())
+    }
+
+    /// Approve a milestone and release its share of the escrow (requester)
+    pub fn approve_milestone(ctx: Context<ApproveMilestone>, index: u8) -> Result<()> {
+        let used_session = authorize_agent(
+            &ctx.accounts.requester_agent,
+            ctx.accounts.requester.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_APPROVE,
+        )?;
+
+        require!(
+            ctx.accounts.escrow.status == STATUS_IN_PROGRESS,
+            AgentLinkError::InvalidJobStatus
+        );
+        if used_session {
+            let amount = ctx
+                .accounts
+                .escrow
+                .milestones
+                .get(index as usize)
+                .ok_or(AgentLinkError::InvalidMilestone)?
+                .amount;
+            charge_session_payout(&mut ctx.accounts.session, &ctx.accounts.escrow, amount)?;
+        }
+
+        release_milestone(
+            &mut ctx.accounts.escrow,
+            &mut ctx.accounts.worker_agent,
+            &mut ctx.accounts.requester_agent,
+            &mut ctx.accounts.pair,
+            &ctx.accounts.worker_creator,
+            &ctx.accounts.treasury.to_account_info(),
+            &ctx.accounts.config,
+            index,
+            false,
+        )
+    }
+
+    /// Claim a milestone payment after its review timeout (anyone can call)
+    pub fn claim_milestone_timeout(
+        ctx: Context<ClaimMilestoneTimeout>,
+        index: u8,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let escrow = &ctx.accounts.escrow;
+
+        require!(
+            escrow.status == STATUS_IN_PROGRESS,
+            AgentLinkError::InvalidJobStatus
+        );
+
+        let milestone = escrow
+            .milestones
+            .get(index as usize)
+            .ok_or(AgentLinkError::InvalidMilestone)?;
+        require!(
+            milestone.status == MILESTONE_SUBMITTED,
+            AgentLinkError::InvalidMilestoneStatus
+        );
+        require!(
+            clock.unix_timestamp > milestone.submitted_at + (escrow.review_hours as i64 * 3600),
+            AgentLinkError::DeadlineNotReached
+        );
+
+        release_milestone(
+            &mut ctx.accounts.escrow,
+            &mut ctx.accounts.worker_agent,
+            &mut ctx.accounts.requester_agent,
+            &mut ctx.accounts.pair,
+            &ctx.accounts.worker_creator,
+            &ctx.accounts.treasury.to_account_info(),
+            &ctx.accounts.config,
+            index,
+            true,
+        )
+    }
+
+    /// Cancel job and refund (requester, only if status is Open)
+    pub fn cancel_job(ctx: Context<CancelJob>) -> Result<()> {
+        let used_session = authorize_agent(
+            &ctx.accounts.requester_agent,
+            ctx.accounts.requester.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_CANCEL,
+        )?;
+
+        require!(ctx.accounts.escrow.status == STATUS_OPEN, AgentLinkError::InvalidJobStatus);
+
+        // Get refund amount
+        let refund_amount = ctx.accounts.escrow.amount;
+
+        // Do lamport transfer first
+        refund_requester(
+            &ctx.accounts.escrow,
+            &ctx.accounts.requester_agent,
+            &ctx.accounts.requester,
+            used_session,
+            refund_amount,
+        )?;
+
+        // Now update account data
+        let requester_agent = &mut ctx.accounts.requester_agent;
+        requester_agent.total_spent = requester_agent.total_spent.checked_sub(refund_amount).unwrap();
+        requester_agent.cancellations = requester_agent.cancellations.checked_add(1).unwrap();
+        refresh_requester_score(requester_agent);
+
+        let escrow = &mut ctx.accounts.escrow;
+        let job_id = escrow.job_id.clone();
+        escrow.status = STATUS_CANCELLED;
+        escrow.amount = 0;
+
+        emit!(JobCancelled {
+            escrow: escrow.key(),
+            job_id: job_id.clone(),
+            requester: escrow.requester,
+            mint: escrow.mint,
+            refund_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Job '{}' cancelled, {} lamports refunded", job_id, refund_amount);
+
+        Ok(())
+    }
+
+    /// Reclaim a job whose hired worker missed the delivery deadline
+    /// (requester). Either refunds the escrow or reopens the job for a new hire.
+    pub fn reclaim_expired(ctx: Context<ReclaimExpired>, reopen: bool) -> Result<()> {
+        let used_session = authorize_agent(
+            &ctx.accounts.requester_agent,
+            ctx.accounts.requester.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_CANCEL,
+        )?;
+
+        let clock = Clock::get()?;
+        let escrow = &ctx.accounts.escrow;
+
+        require!(
+            escrow.status == STATUS_IN_PROGRESS,
+            AgentLinkError::InvalidJobStatus
+        );
+        require!(
+            clock.unix_timestamp > escrow.deadline,
+            AgentLinkError::DeadlineNotReached
+        );
+        require!(
+            !escrow
+                .milestones
+                .iter()
+                .any(|m| m.status == MILESTONE_SUBMITTED),
+            AgentLinkError::MilestonePendingApproval
+        );
+        require!(escrow.open_subjobs == 0, AgentLinkError::SubjobsOpen);
+        // Token escrows are refunded by reopening and then calling cancel_job_token
+        require!(reopen || escrow.is_native(), AgentLinkError::TokenEscrow);
+
+        // Record the no-show against the worker
+        let worker_agent = &mut ctx.accounts.worker_agent;
+        worker_agent.no_shows = worker_agent.no_shows.checked_add(1).unwrap();
+        update_reputation(worker_agent, clock.unix_timestamp);
+
+        // Slash the worker's bond to the requester
+        settle_bond(
+            &mut ctx.accounts.escrow,
+            &ctx.accounts.requester_agent.to_account_info(),
+            true,
+        )?;
+
+        if reopen {
+            let escrow = &mut ctx.accounts.escrow;
+            escrow.worker = Pubkey::default();
+            escrow.status = STATUS_OPEN;
+            escrow.deadline = 0;
+
+            emit!(JobExpired {
+                escrow: escrow.key(),
+                job_id: escrow.job_id.clone(),
+                requester: escrow.requester,
+                worker: ctx.accounts.worker_agent.key(),
+                reopened: true,
+                refund_amount: 0,
+                timestamp: clock.unix_timestamp,
+            });
+
+            msg!("Job '{}' expired and reopened for hire", escrow.job_id);
+
+            return Ok(());
+        }
+
+        // Get refund amount
+        let refund_amount = ctx.accounts.escrow.amount;
+
+        // Do lamport transfer first
+        refund_requester(
+            &ctx.accounts.escrow,
+            &ctx.accounts.requester_agent,
+            &ctx.accounts.requester,
+            used_session,
+            refund_amount,
+        )?;
+
+        // Now update account data
+        let requester_agent = &mut ctx.accounts.requester_agent;
+        requester_agent.total_spent = requester_agent.total_spent.checked_sub(refund_amount).unwrap();
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.status = STATUS_CANCELLED;
+        escrow.amount = 0;
+
+        emit!(JobExpired {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            requester: escrow.requester,
+            worker: escrow.worker,
+            reopened: false,
+            refund_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Job '{}' expired, {} lamports refunded",
+            escrow.job_id,
+            refund_amount
+        );
+
+        Ok(())
+    }
+
+    /// Recompute an agent's reputation so inactivity decay is reflected
+    /// (anyone can call)
+    pub fn refresh_reputation(ctx: Context<RefreshReputation>) -> Result<()> {
+        let agent = &mut ctx.accounts.agent;
+        let old_score = agent.reputation_score;
+        update_reputation(agent, Clock::get()?.unix_timestamp);
+
+        emit!(ReputationRefreshed {
+            agent: agent.key(),
+            old_score,
+            new_score: agent.reputation_score,
+        });
+
+        msg!(
+            "Agent '{}' reputation refreshed: {} -> {}",
+            agent.name,
+            old_score,
+            agent.reputation_score
+        );
+
+        Ok(())
+    }
+
+    /// Dispute a job (either party)
+    pub fn dispute_job(ctx: Context<DisputeJob>) -> Result<()> {
+        authorize_agent(
+            &ctx.accounts.caller_agent,
+            ctx.accounts.caller.key(),
+            ctx.accounts.session.as_deref(),
+            SESSION_DISPUTE,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+
+        require!(
+            escrow.status == STATUS_IN_PROGRESS
+                || escrow.status == STATUS_PENDING_APPROVAL
+                || escrow.status == STATUS_AWAITING_KEY,
+            AgentLinkError::InvalidJobStatus
+        );
+        // A worker withholding its key gets the review window to reveal first
+        if escrow.status == STATUS_AWAITING_KEY {
+            require!(
+                Clock::get()?.unix_timestamp > escrow.key_reveal_deadline(),
+                AgentLinkError::DeadlineNotReached
+            );
+        }
+        // Once the key is public the requester has the result, so the
+        // worker's payout can no longer be contested
+        require!(!escrow.key_revealed, AgentLinkError::KeyAlreadyRevealed);
+
+        // Verify caller is either requester or worker
+        let caller = ctx.accounts.caller.key();
+        let caller_agent = ctx.accounts.caller_agent.key();
+        require!(
+            caller_agent == escrow.requester || caller_agent == escrow.worker,
+            AgentLinkError::Unauthorized
+        );
+
+        let previous_status = escrow.status;
+        escrow.status = STATUS_DISPUTED;
+
+        // Track disputes raised by requesters
+        if caller_agent == escrow.requester {
+            let requester_agent = &mut ctx.accounts.caller_agent;
+            requester_agent.disputes_raised = requester_agent.disputes_raised.checked_add(1).unwrap();
+            refresh_requester_score(requester_agent);
+        }
+
+        emit!(JobDisputed {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            disputed_by: caller_agent,
+            caller,
+            previous_status,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Job '{}' disputed by {}", escrow.job_id, caller);
+
+        Ok(())
+    }
+
+    /// Initialize the global protocol config and treasury (program upgrade authority)
+    pub fn initialize_config(ctx: Context<InitializeConfig>, protocol_fee_bps: u16) -> Result<()> {
+        require!(
+            protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS,
+            AgentLinkError::ProtocolFeeTooHigh
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.bump = ctx.bumps.config;
+        config.admin = ctx.accounts.admin.key();
+        config.treasury = ctx.accounts.treasury.key();
+        config.max_split_bps = MAX_SPLIT_BPS;
+        config.default_split_bps = DEFAULT_SPLIT_BPS;
+        config.verification_threshold = VERIFICATION_THRESHOLD;
+        config.allowed_timeouts = DEFAULT_TIMEOUTS;
+        config.protocol_fee_bps = protocol_fee_bps;
+        config.paused = false;
+        config.min_reputation_job_value = DEFAULT_MIN_REPUTATION_JOB_VALUE;
+        config.reputation_mints = Vec::new();
+
+        ctx.accounts.treasury.bump = ctx.bumps.treasury;
+
+        emit!(ConfigUpdated {
+            admin: config.admin,
+            max_split_bps: config.max_split_bps,
+            default_split_bps: config.default_split_bps,
+            verification_threshold: config.verification_threshold,
+            allowed_timeouts: config.allowed_timeouts,
+            protocol_fee_bps: config.protocol_fee_bps,
+            min_reputation_job_value: config.min_reputation_job_value,
+            reputation_mints: config.reputation_mints.clone(),
+        });
+
+        msg!(
+            "Protocol config initialized by {} with {}bps fee",
+            config.admin,
+            protocol_fee_bps
+        );
+
+        Ok(())
+    }
+
+    /// Update protocol parameters (admin)
+    pub fn update_config(ctx: Context<UpdateConfig>, params: ConfigParams) -> Result<()> {
+        require!(
+            params.max_split_bps <= BPS_DENOMINATOR,
+            AgentLinkError::SplitTooHigh
+        );
+        require!(
+            params.default_split_bps <= params.max_split_bps,
+            AgentLinkError::SplitTooHigh
+        );
+        require!(
+            params.protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS,
+            AgentLinkError::ProtocolFeeTooHigh
+        );
+        require!(
+            params.verification_threshold > 0,
+            AgentLinkError::InvalidConfig
+        );
+        require!(
+            params.allowed_timeouts.iter().any(|hours| *hours > 0),
+            AgentLinkError::InvalidConfig
+        );
+        require!(
+            params.reputation_mints.len() <= MAX_REPUTATION_MINTS
+                && params
+                    .reputation_mints
+                    .iter()
+                    .all(|m| m.mint != Pubkey::default() && m.min_job_value > 0),
+            AgentLinkError::InvalidConfig
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.max_split_bps = params.max_split_bps;
+        config.default_split_bps = params.default_split_bps;
+        config.verification_threshold = params.verification_threshold;
+        config.allowed_timeouts = params.allowed_timeouts;
+        config.protocol_fee_bps = params.protocol_fee_bps;
+        config.min_reputation_job_value = params.min_reputation_job_value;
+        config.reputation_mints = params.reputation_mints;
+
+        emit!(ConfigUpdated {
+            admin: config.admin,
+            max_split_bps: config.max_split_bps,
+            default_split_bps: config.default_split_bps,
+            verification_threshold: config.verification_threshold,
+            allowed_timeouts: config.allowed_timeouts,
+            protocol_fee_bps: config.protocol_fee_bps,
+            min_reputation_job_value: config.min_reputation_job_value,
+            reputation_mints: config.reputation_mints.clone(),
+        });
+
+        msg!("Protocol config updated by {}", config.admin);
+
+        Ok(())
+    }
+
+    /// Pause or unpause job creation, hiring, settlement and withdrawals (admin)
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.paused = paused;
+
+        let clock = Clock::get()?;
+        emit!(PauseUpdated {
+            admin: config.admin,
+            paused,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Program {} by {}",
+            if paused { "paused" } else { "unpaused" },
+            config.admin
+        );
+
+        Ok(())
+    }
+
+    /// Withdraw collected protocol fees from the treasury (admin)
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        // Get treasury balance (excluding rent)
+        let rent = Rent::get()?;
+        let min_balance = rent.minimum_balance(Treasury::SPACE);
+        let treasury_balance = ctx.accounts.treasury.to_account_info().lamports();
+        let available = treasury_balance.saturating_sub(min_balance);
+
+        let withdraw_amount = if amount == 0 { available } else { amount };
+        require!(withdraw_amount <= available, AgentLinkError::InsufficientFunds);
+        require!(withdraw_amount > 0, AgentLinkError::NothingToWithdraw);
+
+        // Transfer from treasury PDA to admin
+        **ctx.accounts.treasury.to_account_info().try_borrow_mut_lamports()? -= withdraw_amount;
+        **ctx.accounts.admin.to_account_info().try_borrow_mut_lamports()? += withdraw_amount;
+
+        emit!(TreasuryWithdrawn {
+            admin: ctx.accounts.admin.key(),
+            destination: ctx.accounts.admin.key(),
+            mint: Pubkey::default(),
+            amount: withdraw_amount,
+        });
+
+        msg!(
+            "Withdrew {} lamports from treasury to {}",
+            withdraw_amount,
+            ctx.accounts.admin.key()
+        );
+
+        Ok(())
+    }
+
+    /// Withdraw collected token fees from a treasury token account (admin)
+    pub fn withdraw_treasury_token(ctx: Context<WithdrawTreasuryToken>, amount: u64) -> Result<()> {
+        let available = ctx.accounts.treasury_token.amount;
+
+        let withdraw_amount = if amount == 0 { available } else { amount };
+        require!(withdraw_amount <= available, AgentLinkError::InsufficientFunds);
+        require!(withdraw_amount > 0, AgentLinkError::NothingToWithdraw);
+
+        // Transfer from treasury token account, signed by the treasury PDA
+        let seeds: &[&[u8]] = &[b"treasury", &[ctx.accounts.treasury.bump]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.treasury_token.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.treasury.to_account_info(),
+                },
+                &[seeds],
+            ),
+            withdraw_amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        emit!(TreasuryWithdrawn {
+            admin: ctx.accounts.admin.key(),
+            destination: ctx.accounts.destination.key(),
+            mint: ctx.accounts.mint.key(),
+            amount: withdraw_amount,
+        });
+
+        msg!(
+            "Withdrew {} tokens of mint {} from treasury to {}",
+            withdraw_amount,
+            ctx.accounts.mint.key(),
+            ctx.accounts.destination.key()
+        );
+
+        Ok(())
+    }
+
+    /// Register a dispute arbiter (admin)
+    pub fn register_arbiter(ctx: Context<RegisterArbiter>) -> Result<()> {
+        let arbiter = &mut ctx.accounts.arbiter;
+        let clock = Clock::get()?;
+
+        arbiter.bump = ctx.bumps.arbiter;
+        arbiter.authority = ctx.accounts.arbiter_authority.key();
+        arbiter.registered_by = ctx.accounts.admin.key();
+        arbiter.active = true;
+        arbiter.disputes_resolved = 0;
+        arbiter.created_at = clock.unix_timestamp;
+
+        emit!(ArbiterRegistered {
+            arbiter: arbiter.key(),
+            authority: arbiter.authority,
+            registered_by: arbiter.registered_by,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Arbiter {} registered", arbiter.authority);
+
+        Ok(())
+    }
+
+    /// Activate or deactivate an arbiter (admin)
+    pub fn set_arbiter_active(ctx: Context<SetArbiterActive>, active: bool) -> Result<()> {
+        let arbiter = &mut ctx.accounts.arbiter;
+        arbiter.active = active;
+
+        emit!(ArbiterUpdated {
+            arbiter: arbiter.key(),
+            authority: arbiter.authority,
+            active,
+        });
+
+        msg!("Arbiter {} active: {}", arbiter.authority, active);
+
+        Ok(())
+    }
+
+    /// Assign an arbiter to a disputed job (admin)
+    pub fn assign_arbiter(ctx: Context<AssignArbiter>) -> Result<()> {
+        require!(ctx.accounts.arbiter.active, AgentLinkError::ArbiterInactive);
+
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.status == STATUS_DISPUTED, AgentLinkError::InvalidJobStatus);
+
+        escrow.arbiter = ctx.accounts.arbiter.key();
+
+        emit!(ArbiterAssigned {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            arbiter: escrow.arbiter,
+            arbiter_authority: ctx.accounts.arbiter.authority,
+        });
+
+        msg!(
+            "Arbiter {} assigned to job '{}'",
+            ctx.accounts.arbiter.authority,
+            escrow.job_id
+        );
+
+        Ok(())
+    }
+
+    /// Resolve a disputed job by splitting the escrow (assigned arbiter)
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, worker_share_bps: u16) -> Result<()> {
+        require!(
+            ctx.accounts.escrow.status == STATUS_DISPUTED,
+            AgentLinkError::InvalidJobStatus
+        );
+        require!(ctx.accounts.arbiter.active, AgentLinkError::ArbiterInactive);
+        require!(
+            ctx.accounts.escrow.open_subjobs == 0,
+            AgentLinkError::SubjobsOpen
+        );
+        require!(
+            worker_share_bps <= BPS_DENOMINATOR,
+            AgentLinkError::InvalidShare
+        );
+
+        // Arbiter cannot rule on a job it is party to
+        let arbiter_authority = ctx.accounts.arbiter.authority;
+        require!(
+            arbiter_authority != ctx.accounts.worker_agent.authority
+                && arbiter_authority != ctx.accounts.requester_agent.authority,
+            AgentLinkError::ArbiterConflict
+        );
+
+        // Extract values we need
+        let escrow_amount = ctx.accounts.escrow.amount;
+
+        // Calculate worker share and requester refund
+        let worker_total = (escrow_amount as u128)
+            .checked_mul(worker_share_bps as u128)
+            .unwrap()
+            .checked_div(BPS_DENOMINATOR as u128)
+            .unwrap() as u64;
+        let refund_amount = escrow_amount.checked_sub(worker_total).unwrap();
+
+        // Do lamport transfers first (arbitrated rulings carry no protocol fee)
+        let (_, creator_amount, worker_amount) = payout_worker(
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.worker_agent,
+            &ctx.accounts.worker_creator.to_account_info(),
+            None,
+            worker_total,
+            0,
+        )?;
+
+        if refund_amount > 0 {
+            refund_requester(
+                &ctx.accounts.escrow,
+                &ctx.accounts.requester_agent,
+                &ctx.accounts.requester,
+                false,
+                refund_amount,
+            )?;
+        }
+
+        // Now update account data. A majority ruling for the worker counts
+        // as a successful job; anything else only credits what was earned.
+        if worker_share_bps > BPS_DENOMINATOR / 2 {
+            let weight_bps = record_pair_job(
+                &mut ctx.accounts.pair,
+                escrow_amount,
+                &ctx.accounts.escrow.mint,
+                &ctx.accounts.config,
+            );
+            let verification_threshold = ctx.accounts.config.verification_threshold;
+            record_successful_job(
+                &mut ctx.accounts.worker_agent,
+                worker_total,
+                weight_bps,
+                verification_threshold,
+            )?;
+        } else if worker_total > 0 {
+            record_earnings(&mut ctx.accounts.worker_agent, worker_total)?;
+        }
+
+        record_dispute_outcome(
+            &mut ctx.accounts.worker_agent,
+            &mut ctx.accounts.requester_agent,
+            worker_share_bps,
+        )?;
+
+        // A worker who loses the ruling forfeits the bond to the requester
+        if worker_share_bps < BPS_DENOMINATOR / 2 {
+            settle_bond(
+                &mut ctx.accounts.escrow,
+                &ctx.accounts.requester_agent.to_account_info(),
+                true,
+            )?;
+        } else {
+            settle_bond(
+                &mut ctx.accounts.escrow,
+                &ctx.accounts.worker_agent.to_account_info(),
+                false,
+            )?;
+        }
+
+        let requester_agent = &mut ctx.accounts.requester_agent;
+        requester_agent.total_spent = requester_agent.total_spent.checked_sub(refund_amount).unwrap();
+
+        let arbiter = &mut ctx.accounts.arbiter;
+        arbiter.disputes_resolved = arbiter.disputes_resolved.checked_add(1).unwrap();
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.status = STATUS_RESOLVED;
+
+        emit!(DisputeResolved {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            arbiter: escrow.arbiter,
+            requester: escrow.requester,
+            worker: escrow.worker,
+            mint: escrow.mint,
+            worker_share_bps,
+            worker_amount,
+            creator_amount,
+            refund_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Job '{}' resolved. Worker received {} lamports, creator received {} lamports, requester refunded {} lamports",
+            escrow.job_id,
+            worker_amount,
+            creator_amount,
+            refund_amount
+        );
+
+        Ok(())
+    }
+
+    /// Configure creator split percentage
+    pub fn configure_split(ctx: Context<ConfigureSplit>, new_split_bps: u16) -> Result<()> {
+        require!(
+            new_split_bps <= ctx.accounts.config.max_split_bps,
+            AgentLinkError::SplitTooHigh
+        );
+
+        let agent = &mut ctx.accounts.agent;
+        let old_split_bps = agent.creator_split_bps;
+        agent.creator_split_bps = new_split_bps;
+
+        emit!(SplitConfigured {
+            agent: agent.key(),
+            authority: ctx.accounts.authority.key(),
+            old_split_bps,
+            new_split_bps,
+        });
+
+        msg!("Agent '{}' split updated to {}bps", agent.name, new_split_bps);
+
+        Ok(())
+    }
+
+    /// Publish an agent's metadata URI, content hash and capability bitmap
+    /// (authority)
+    pub fn update_agent_profile(
+        ctx: Context<UpdateAgentProfile>,
+        metadata_uri: String,
+        metadata_hash: [u8; 32],
+        capabilities: u64,
+    ) -> Result<()> {
+        require!(
+            metadata_uri.len() <= MAX_METADATA_URI_LENGTH,
+            AgentLinkError::MetadataUriTooLong
+        );
+
+        let agent = &mut ctx.accounts.agent;
+        agent.metadata_uri = metadata_uri;
+        agent.metadata_hash = metadata_hash;
+        agent.capabilities = capabilities;
+
+        emit!(AgentProfileUpdated {
+            agent: agent.key(),
+            metadata_uri: agent.metadata_uri.clone(),
+            metadata_hash,
+            capabilities,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Agent '{}' profile updated: {}",
+            agent.name,
+            agent.metadata_uri
+        );
+
+        Ok(())
+    }
+
+    /// Propose a new authority for an agent (current authority)
+    pub fn propose_authority(
+        ctx: Context<ProposeAuthority>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let agent = &mut ctx.accounts.agent;
+
+        // Proposing the default key clears a pending transfer
+        require!(
+            new_authority != agent.authority,
+            AgentLinkError::InvalidAuthority
+        );
+        agent.pending_authority = new_authority;
+
+        emit!(AuthorityProposed {
+            agent: agent.key(),
+            authority: agent.authority,
+            pending_authority: new_authority,
+        });
+
+        msg!(
+            "Agent '{}' authority transfer proposed to {}",
+            agent.name,
+            new_authority
+        );
+
+        Ok(())
+    }
+
+    /// Accept a pending authority transfer (pending authority)
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let agent = &mut ctx.accounts.agent;
+        let old_authority = agent.authority;
+        agent.authority = ctx.accounts.new_authority.key();
+        agent.pending_authority = Pubkey::default();
+
+        let clock = Clock::get()?;
+        emit!(AuthorityTransferred {
+            agent: agent.key(),
+            old_authority,
+            new_authority: agent.authority,
+            recovered: false,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Agent '{}' authority transferred to {}",
+            agent.name,
+            agent.authority
+        );
+
+        Ok(())
+    }
+
+    /// Reset an agent's authority without its consent (creator override)
+    pub fn recover_authority(
+        ctx: Context<RecoverAuthority>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        require!(
+            new_authority != Pubkey::default(),
+            AgentLinkError::InvalidAuthority
+        );
+
+        let agent = &mut ctx.accounts.agent;
+        let old_authority = agent.authority;
+        agent.authority = new_authority;
+        agent.pending_authority = Pubkey::default();
+
+        let clock = Clock::get()?;
+        emit!(AuthorityTransferred {
+            agent: agent.key(),
+            old_authority,
+            new_authority,
+            recovered: true,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Agent '{}' authority recovered by creator to {}",
+            agent.name,
+            new_authority
+        );
+
+        Ok(())
+    }
+
+    /// Create a scoped session key that can sign job instructions for an
+    /// agent (authority). Session keys can never withdraw or change the split.
+    pub fn create_session(
+        ctx: Context<CreateSession>,
+        key: Pubkey,
+        expires_at: i64,
+        allowed_ixs: u16,
+        spend_cap: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(expires_at > clock.unix_timestamp, AgentLinkError::InvalidSession);
+        require!(
+            allowed_ixs != 0 && allowed_ixs & !SESSION_ALL == 0,
+            AgentLinkError::InvalidSession
+        );
+        require!(
+            key != ctx.accounts.agent.authority,
+            AgentLinkError::InvalidSession
+        );
+
+        let session = &mut ctx.accounts.session;
+        session.bump = ctx.bumps.session;
+        session.agent = ctx.accounts.agent.key();
+        session.authority = ctx.accounts.authority.key();
+        session.key = key;
+        session.expires_at = expires_at;
+        session.allowed_ixs = allowed_ixs;
+        session.spend_cap = spend_cap;
+        session.spent = 0;
+        session.created_at = clock.unix_timestamp;
+
+        emit!(SessionCreated {
+            session: session.key(),
+            agent: session.agent,
+            key,
+            expires_at,
+            allowed_ixs,
+            spend_cap,
+        });
+
+        msg!(
+            "Session key {} created for agent '{}', expires {}",
+            key,
+            ctx.accounts.agent.name,
+            expires_at
+        );
+
+        Ok(())
+    }
+
+    /// Revoke a session key and reclaim its rent (authority)
+    pub fn revoke_session(ctx: Context<RevokeSession>) -> Result<()> {
+        let session = &ctx.accounts.session;
+
+        emit!(SessionRevoked {
+            session: session.key(),
+            agent: session.agent,
+            key: session.key,
+        });
+
+        msg!(
+            "Session key {} revoked for agent '{}'",
+            session.key,
+            ctx.accounts.agent.name
+        );
+
+        Ok(())
+    }
+
+    /// Withdraw earnings from agent PDA
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        // Get agent PDA balance (excluding rent)
+        let rent = Rent::get()?;
+        let min_balance = rent.minimum_balance(AgentAccount::SPACE);
+        let agent_balance = ctx.accounts.agent.to_account_info().lamports();
+        let available = agent_balance.saturating_sub(min_balance);
+
+        let withdraw_amount = if amount == 0 { available } else { amount };
+        require!(withdraw_amount <= available, AgentLinkError::InsufficientFunds);
+        require!(withdraw_amount > 0, AgentLinkError::NothingToWithdraw);
+
+        // Transfer from agent PDA to authority
+        **ctx.accounts.agent.to_account_info().try_borrow_mut_lamports()? -= withdraw_amount;
+        **ctx.accounts.authority.to_account_info().try_borrow_mut_lamports()? += withdraw_amount;
+
+        emit!(Withdrawn {
+            agent: ctx.accounts.agent.key(),
+            authority: ctx.accounts.authority.key(),
+            destination: ctx.accounts.authority.key(),
+            mint: Pubkey::default(),
+            amount: withdraw_amount,
+        });
+
+        msg!(
+            "Withdrew {} lamports from agent '{}' to {}",
+            withdraw_amount,
+            ctx.accounts.agent.name,
+            ctx.accounts.authority.key()
+        );
+
+        Ok(())
+    }
+
+    /// Close a settled escrow and return its rent to the original payer
+    /// (anyone can call)
+    pub fn close_escrow(ctx: Context<CloseEscrow>) -> Result<()> {
+        require!(
+            ctx.accounts.escrow.is_terminal(),
+            AgentLinkError::InvalidJobStatus
+        );
+        require!(
+            ctx.accounts.escrow.service == Pubkey::default(),
+            AgentLinkError::ServiceSlotHeld
+        );
+        require!(
+            !ctx.accounts.escrow.parent_open,
+            AgentLinkError::SubjobNotSettled
+        );
+        require!(
+            ctx.accounts.escrow.open_subjobs == 0,
+            AgentLinkError::SubjobsOpen
+        );
+
+        emit!(EscrowClosed {
+            escrow: ctx.accounts.escrow.key(),
+            job_id: ctx.accounts.escrow.job_id.clone(),
+            payer: ctx.accounts.payer.key(),
+            rent_returned: ctx.accounts.escrow.to_account_info().lamports(),
+        });
+
+        msg!(
+            "Escrow for job '{}' closed, rent returned to {}",
+            ctx.accounts.escrow.job_id,
+            ctx.accounts.payer.key()
+        );
+
+        Ok(())
+    }
+
+    /// Create a new job with an SPL Token / Token-2022 escrow
+    pub fn create_job_token(
+        ctx: Context<CreateJobToken>,
+        job_id: String,
+        job_hash: [u8; 32],
+        amount: u64,
+        timeout_hours: u8,
+        review_hours: u8,
+    ) -> Result<()> {
+        require!(job_id.len() <= MAX_JOB_ID_LENGTH, AgentLinkError::JobIdTooLong);
+        require!(amount > 0, AgentLinkError::InvalidAmount);
+        require!(
+            ctx.accounts.config.is_allowed_timeout(timeout_hours),
+            AgentLinkError::InvalidTimeout
+        );
+        require!(
+            ctx.accounts.config.is_allowed_timeout(review_hours),
+            AgentLinkError::InvalidReviewWindow
+        );
+
+        let clock = Clock::get()?;
+
+        // Transfer tokens from requester to the escrow vault first
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.requester_token.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.requester.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        // Token-2022 transfer fees are withheld from the destination, so
+        // escrow only what the vault actually received
+        ctx.accounts.vault.reload()?;
+        let received = ctx.accounts.vault.amount;
+        require!(received > 0, AgentLinkError::InvalidAmount);
+
+        // Now set escrow fields
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.init(
+            ctx.bumps.escrow,
+            job_id.clone(),
+            job_hash,
+            ctx.accounts.requester_agent.key(),
+            ctx.accounts.requester.key(),
+            received,
+            timeout_hours,
+            review_hours,
+            clock.unix_timestamp,
+        );
+        escrow.mint = ctx.accounts.mint.key();
+
+        emit!(JobCreated {
+            escrow: escrow.key(),
+            job_id: job_id.clone(),
+            job_hash,
+            requester: escrow.requester,
+            payer: escrow.payer,
+            mint: escrow.mint,
+            amount: received,
+            timeout_hours,
+            review_hours,
+            milestone_count: 0,
+            required_bond: 0,
+            hire_mode: HIRE_MODE_MANUAL,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Job '{}' created with {} tokens of mint {} in escrow",
+            job_id,
+            received,
+            escrow.mint
+        );
+
+        Ok(())
+    }
+
+    /// Approve a token job and release payment (requester)
+    pub fn approve_job_token(ctx: Context<ApproveJobToken>) -> Result<()> {
+        require!(
+            ctx.accounts.escrow.status == STATUS_PENDING_APPROVAL,
+            AgentLinkError::InvalidJobStatus
+        );
+
+        // Extract values we need before any mutable borrows
+        let escrow_amount = ctx.accounts.escrow.amount;
+
+        // Do token transfers first
+        let (protocol_fee, creator_amount, worker_amount) = payout_worker_token(
+            &ctx.accounts.escrow,
+            &ctx.accounts.vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.worker_agent,
+            &ctx.accounts.worker_token,
+            &ctx.accounts.worker_creator_token,
+            Some(&ctx.accounts.treasury_token),
+            &ctx.accounts.token_program,
+            escrow_amount,
+            ctx.accounts.config.protocol_fee_bps,
+        )?;
+
+        // Now update account data (total_earned only tracks lamports; the job
+        // carries reputation weight only if its mint is allowlisted)
+        let weight_bps = record_pair_job(
+            &mut ctx.accounts.pair,
+            escrow_amount,
+            &ctx.accounts.escrow.mint,
+            &ctx.accounts.config,
+        );
+        let verification_threshold = ctx.accounts.config.verification_threshold;
+        record_successful_job(&mut ctx.accounts.worker_agent, 0, weight_bps, verification_threshold)?;
+
+        let completed_at = ctx.accounts.escrow.completed_at;
+        record_requester_payment(
+            &mut ctx.accounts.requester_agent,
+            completed_at,
+            Clock::get()?.unix_timestamp,
+            false,
+        );
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.status = STATUS_COMPLETED;
+
+        emit!(JobApproved {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            requester: escrow.requester,
+            worker: escrow.worker,
+            worker_creator: ctx.accounts.worker_agent.creator,
+            mint: escrow.mint,
+            result_hash: escrow.result_hash,
+            amount: escrow_amount,
+            protocol_fee,
+            worker_amount,
+            creator_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Job '{}' approved. Worker received {} tokens, creator received {} tokens",
+            escrow.job_id,
+            worker_amount,
+            creator_amount
+        );
+
+        Ok(())
+    }
+
+    /// Claim token payment after the review window lapses (anyone can call)
+    pub fn claim_timeout_token(ctx: Context<ClaimTimeoutToken>) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(
+            ctx.accounts.escrow.status == STATUS_PENDING_APPROVAL,
+            AgentLinkError::InvalidJobStatus
+        );
+        require!(
+            clock.unix_timestamp > ctx.accounts.escrow.review_deadline(),
+            AgentLinkError::DeadlineNotReached
+        );
+
+        // Extract values we need
+        let escrow_amount = ctx.accounts.escrow.amount;
+
+        // Do token transfers first
+        let (protocol_fee, creator_amount, worker_amount) = payout_worker_token(
+            &ctx.accounts.escrow,
+            &ctx.accounts.vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.worker_agent,
+            &ctx.accounts.worker_token,
+            &ctx.accounts.worker_creator_token,
+            Some(&ctx.accounts.treasury_token),
+            &ctx.accounts.token_program,
+            escrow_amount,
+            ctx.accounts.config.protocol_fee_bps,
+        )?;
+
+        // Now update account data (total_earned only tracks lamports; the job
+        // carries reputation weight only if its mint is allowlisted)
+        let weight_bps = record_pair_job(
+            &mut ctx.accounts.pair,
+            escrow_amount,
+            &ctx.accounts.escrow.mint,
+            &ctx.accounts.config,
+        );
+        let verification_threshold = ctx.accounts.config.verification_threshold;
+        record_successful_job(&mut ctx.accounts.worker_agent, 0, weight_bps, verification_threshold)?;
+
+        let completed_at = ctx.accounts.escrow.completed_at;
+        record_requester_payment(
+            &mut ctx.accounts.requester_agent,
+            completed_at,
+            clock.unix_timestamp,
+            true,
+        );
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.status = STATUS_COMPLETED;
+
+        emit!(TimeoutClaimed {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            requester: escrow.requester,
+            worker: escrow.worker,
+            worker_creator: ctx.accounts.worker_agent.creator,
+            caller: ctx.accounts.caller.key(),
+            mint: escrow.mint,
+            result_hash: escrow.result_hash,
+            amount: escrow_amount,
+            protocol_fee,
+            worker_amount,
+            creator_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Job '{}' auto-released after timeout. Worker received {} tokens",
+            escrow.job_id,
+            worker_amount
+        );
+
+        Ok(())
+    }
+
+    /// Cancel token job and refund (requester, only if status is Open)
+    pub fn cancel_job_token(ctx: Context<CancelJobToken>) -> Result<()> {
+        require!(ctx.accounts.escrow.status == STATUS_OPEN, AgentLinkError::InvalidJobStatus);
+
+        // Get refund amount
+        let refund_amount = ctx.accounts.escrow.amount;
+
+        // Do token transfer first
+        transfer_from_vault(
+            &ctx.accounts.escrow,
+            &ctx.accounts.vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.requester_token,
+            &ctx.accounts.token_program,
+            refund_amount,
+        )?;
+
+        let requester_agent = &mut ctx.accounts.requester_agent;
+        requester_agent.cancellations = requester_agent.cancellations.checked_add(1).unwrap();
+        refresh_requester_score(requester_agent);
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.status = STATUS_CANCELLED;
+        escrow.amount = 0;
+
+        emit!(JobCancelled {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            requester: escrow.requester,
+            mint: escrow.mint,
+            refund_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Job '{}' cancelled, {} tokens refunded", escrow.job_id, refund_amount);
+
+        Ok(())
+    }
+
+    /// Resolve a disputed token job by splitting the escrow (assigned arbiter)
+    pub fn resolve_dispute_token(
+        ctx: Context<ResolveDisputeToken>,
+        worker_share_bps: u16,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.escrow.status == STATUS_DISPUTED,
+            AgentLinkError::InvalidJobStatus
+        );
+        require!(ctx.accounts.arbiter.active, AgentLinkError::ArbiterInactive);
+        require!(
+            worker_share_bps <= BPS_DENOMINATOR,
+            AgentLinkError::InvalidShare
+        );
+
+        // Arbiter cannot rule on a job it is party to
+        let arbiter_authority = ctx.accounts.arbiter.authority;
+        require!(
+            arbiter_authority != ctx.accounts.worker_agent.authority
+                && arbiter_authority != ctx.accounts.requester_agent.authority,
+            AgentLinkError::ArbiterConflict
+        );
+
+        // Extract values we need
+        let escrow_amount = ctx.accounts.escrow.amount;
+
+        // Calculate worker share and requester refund
+        let worker_total = (escrow_amount as u128)
+            .checked_mul(worker_share_bps as u128)
+            .unwrap()
+            .checked_div(BPS_DENOMINATOR as u128)
+            .unwrap() as u64;
+        let refund_amount = escrow_amount.checked_sub(worker_total).unwrap();
+
+        // Do token transfers first (arbitrated rulings carry no protocol fee)
+        let (_, creator_amount, worker_amount) = payout_worker_token(
+            &ctx.accounts.escrow,
+            &ctx.accounts.vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.worker_agent,
+            &ctx.accounts.worker_token,
+            &ctx.accounts.worker_creator_token,
+            None,
+            &ctx.accounts.token_program,
+            worker_total,
+            0,
+        )?;
+        transfer_from_vault(
+            &ctx.accounts.escrow,
+            &ctx.accounts.vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.requester_token,
+            &ctx.accounts.token_program,
+            refund_amount,
+        )?;
+
+        // Now update account data (total_earned only tracks lamports; the job
+        // carries reputation weight only if its mint is allowlisted)
+        if worker_share_bps > BPS_DENOMINATOR / 2 {
+            let weight_bps = record_pair_job(
+                &mut ctx.accounts.pair,
+                escrow_amount,
+                &ctx.accounts.escrow.mint,
+                &ctx.accounts.config,
+            );
+            let verification_threshold = ctx.accounts.config.verification_threshold;
+            record_successful_job(
+                &mut ctx.accounts.worker_agent,
+                0,
+                weight_bps,
+                verification_threshold,
+            )?;
+        }
+
+        record_dispute_outcome(
+            &mut ctx.accounts.worker_agent,
+            &mut ctx.accounts.requester_agent,
+            worker_share_bps,
+        )?;
+
+        let arbiter = &mut ctx.accounts.arbiter;
+        arbiter.disputes_resolved = arbiter.disputes_resolved.checked_add(1).unwrap();
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.status = STATUS_RESOLVED;
+
+        emit!(DisputeResolved {
+            escrow: escrow.key(),
+            job_id: escrow.job_id.clone(),
+            arbiter: escrow.arbiter,
+            requester: escrow.requester,
+            worker: escrow.worker,
+            mint: escrow.mint,
+            worker_share_bps,
+            worker_amount,
+            creator_amount,
+            refund_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Job '{}' resolved. Worker received {} tokens, creator received {} tokens, requester refunded {} tokens",
+            escrow.job_id,
+            worker_amount,
+            creator_amount,
+            refund_amount
+        );
+
+        Ok(())
+    }
+
+    /// Withdraw token earnings held by the agent PDA
+    pub fn withdraw_token(ctx: Context<WithdrawToken>, amount: u64) -> Result<()> {
+        let available = ctx.accounts.agent_token.amount;
+
+        let withdraw_amount = if amount == 0 { available } else { amount };
+        require!(withdraw_amount <= available, AgentLinkError::InsufficientFunds);
+        require!(withdraw_amount > 0, AgentLinkError::NothingToWithdraw);
+
+        // Transfer from agent token account, signed by the agent PDA
+        let agent = &ctx.accounts.agent;
+        let seeds: &[&[u8]] = &[
+            b"agent",
+            agent.creator.as_ref(),
+            agent.name.as_bytes(),
+            &[agent.bump],
+        ];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.agent_token.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.agent.to_account_info(),
+                },
+                &[seeds],
+            ),
+            withdraw_amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        emit!(Withdrawn {
+            agent: ctx.accounts.agent.key(),
+            authority: ctx.accounts.authority.key(),
+            destination: ctx.accounts.destination.key(),
+            mint: ctx.accounts.mint.key(),
+            amount: withdraw_amount,
+        });
+
+        msg!(
+            "Withdrew {} tokens of mint {} from agent '{}' to {}",
+            withdraw_amount,
+            ctx.accounts.mint.key(),
+            ctx.accounts.agent.name,
+            ctx.accounts.destination.key()
+        );
+
+        Ok(())
+    }
+
+    /// Close a settled token escrow and its vault, returning rent to the
+    /// original payer (anyone can call). Token-2022 withheld transfer fees
+    /// must be harvested from the vault first.
+    pub fn close_escrow_token(ctx: Context<CloseEscrowToken>) -> Result<()> {
+        require!(
+            ctx.accounts.escrow.is_terminal(),
+            AgentLinkError::InvalidJobStatus
+        );
+
+        // Close the vault, signed by the escrow PDA
+        let escrow = &ctx.accounts.escrow;
+        let bump = [escrow.bump];
+        let seeds = escrow.signer_seeds(&bump);
+        token_interface::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vault.to_account_info(),
+                destination: ctx.accounts.payer.to_account_info(),
+                authority: ctx.accounts.escrow.to_account_info(),
+            },
+            &[&seeds[..]],
+        ))?;
+
+        emit!(EscrowClosed {
+            escrow: ctx.accounts.escrow.key(),
+            job_id: ctx.accounts.escrow.job_id.clone(),
+            payer: ctx.accounts.payer.key(),
+            rent_returned: ctx.accounts.escrow.to_account_info().lamports(),
+        });
+
+        msg!(
+            "Escrow and vault for job '{}' closed, rent returned to {}",
+            ctx.accounts.escrow.job_id,
+            ctx.accounts.payer.key()
+        );
+
+        Ok(())
+    }
+
+    /// Upgrade an agent account written by the original program to the
+    /// current layout (anyone can call; the payer funds the extra rent).
+    /// New counters start at zero and past jobs carry full reputation weight.
+    pub fn migrate_agent(ctx: Context<MigrateAgent>) -> Result<()> {
+        let agent_info = ctx.accounts.agent.to_account_info();
+        let legacy = read_legacy_account::<LegacyAgentAccount>(
+            &agent_info,
+            AgentAccount::DISCRIMINATOR,
+            LegacyAgentAccount::SPACE,
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let agent = AgentAccount {
+            bump: legacy.bump,
+            name: legacy.name,
+            creator: legacy.creator,
+            authority: legacy.authority,
+            created_at: legacy.created_at,
+            creator_signed: legacy.creator_signed,
+            verified: legacy.verified,
+            successful_jobs: legacy.successful_jobs,
+            total_earned: legacy.total_earned,
+            total_spent: legacy.total_spent,
+            reputation_score: legacy.reputation_score,
+            creator_split_bps: legacy.creator_split_bps,
+            no_shows: 0,
+            pending_authority: Pubkey::default(),
+            metadata_uri: String::new(),
+            metadata_hash: [0; 32],
+            capabilities: 0,
+            jobs_paid: 0,
+            timeouts_suffered: 0,
+            disputes_raised: 0,
+            cancellations: 0,
+            total_approval_latency: 0,
+            requester_score: 0,
+            disputes_lost: 0,
+            last_completed_at: 0,
+            weighted_jobs_bps: (legacy.successful_jobs as u64) * (BPS_DENOMINATOR as u64),
+            weighted_earned: legacy.total_earned,
+        };
+
+        grow_legacy_account(
+            &agent_info,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            AgentAccount::SPACE,
+        )?;
+        agent.try_serialize(&mut &mut agent_info.try_borrow_mut_data()?[..])?;
+
+        emit!(AgentMigrated {
+            agent: agent_info.key(),
+            timestamp: now,
+        });
+
+        msg!("Agent '{}' migrated to the current layout", agent.name);
+
+        Ok(())
+    }
+
+    /// Upgrade an escrow written by the original program to the current
+    /// layout (requester). The escrow keeps its `[b"escrow", job_id]`
+    /// address; a pending approval keeps its old timeout by treating the
+    /// delivery window as the review window.
+    pub fn migrate_escrow(ctx: Context<MigrateEscrow>) -> Result<()> {
+        let escrow_info = ctx.accounts.escrow.to_account_info();
+        let legacy = read_legacy_account::<LegacyEscrowAccount>(
+            &escrow_info,
+            EscrowAccount::DISCRIMINATOR,
+            LegacyEscrowAccount::SPACE,
+        )?;
+        require!(
+            legacy.requester == ctx.accounts.requester_agent.key(),
+            AgentLinkError::InvalidRequester
+        );
+        let address = Pubkey::create_program_address(
+            &[b"escrow", legacy.job_id.as_bytes(), &[legacy.bump]],
+            &crate::ID,
+        )
+        .map_err(|_| AgentLinkError::NotLegacyAccount)?;
+        require!(address == escrow_info.key(), AgentLinkError::NotLegacyAccount);
+
+        let mut escrow = EscrowAccount {
+            bump: 0,
+            job_id: String::new(),
+            job_hash: [0; 32],
+            requester: Pubkey::default(),
+            worker: Pubkey::default(),
+            amount: 0,
+            status: STATUS_OPEN,
+            timeout_hours: 0,
+            review_hours: 0,
+            deadline: 0,
+            completed_at: 0,
+            created_at: 0,
+            arbiter: Pubkey::default(),
+            payer: Pubkey::default(),
+            mint: Pubkey::default(),
+            funded_by_agent: false,
+            required_bond: 0,
+            bond: 0,
+            result_hash: [0; 32],
+            result_uri: String::new(),
+            key_commitment: [0; 32],
+            decryption_key: [0; 32],
+            key_revealed: false,
+            approved_at: 0,
+            hire_mode: HIRE_MODE_MANUAL,
+            min_reputation: 0,
+            require_verified: false,
+            min_jobs: 0,
+            hire_after: 0,
+            application_count: 0,
+            reveal_deadline: 0,
+            best_bid: 0,
+            best_bidder: Pubkey::default(),
+            service: Pubkey::default(),
+            parent: Pubkey::default(),
+            parent_open: false,
+            open_subjobs: 0,
+            subjob_total: 0,
+            deadline_cap: 0,
+            seed_version: ESCROW_SEEDS_LEGACY,
+            prerequisites: Vec::new(),
+            met_prerequisites: 0,
+            pipeline: Pubkey::default(),
+            milestones: Vec::new(),
+        };
+        escrow.init(
+            legacy.bump,
+            legacy.job_id,
+            legacy.job_hash,
+            legacy.requester,
+            ctx.accounts.requester.key(),
+            legacy.amount,
+            legacy.timeout_hours,
+            legacy.timeout_hours,
+            legacy.created_at,
+        );
+        escrow.seed_version = ESCROW_SEEDS_LEGACY;
+        escrow.worker = legacy.worker;
+        escrow.status = legacy.status;
+        escrow.deadline = legacy.deadline;
+        if legacy.status == STATUS_PENDING_APPROVAL {
+            escrow.completed_at = legacy.deadline - (legacy.timeout_hours as i64 * 3600);
+        }
+
+        grow_legacy_account(
+            &escrow_info,
+            &ctx.accounts.requester,
+            &ctx.accounts.system_program,
+            EscrowAccount::SPACE,
+        )?;
+        escrow.try_serialize(&mut &mut escrow_info.try_borrow_mut_data()?[..])?;
+
+        emit!(EscrowMigrated {
+            escrow: escrow_info.key(),
+            job_id: escrow.job_id.clone(),
+            requester: escrow.requester,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Escrow for job '{}' migrated to the current layout", escrow.job_id);
+
+        Ok(())
+    }
+}
+
+// Helper function to read an account still in the original program's layout
+fn read_legacy_account<T: AnchorDeserialize>(
+    info: &AccountInfo,
+    discriminator: &[u8],
+    legacy_space: usize,
+) -> Result<T> {
+    require!(info.owner == &crate::ID, AgentLinkError::NotLegacyAccount);
+    let data = info.try_borrow_data()?;
+    require!(
+        data.len() == legacy_space && data[..8] == *discriminator,
+        AgentLinkError::NotLegacyAccount
+    );
+    Ok(T::deserialize(&mut &data[8..])?)
+}
+
+// Helper function to grow a migrated account to its current size, topping up
+// rent from the payer
+fn grow_legacy_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let top_up = rent.saturating_sub(info.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    info.resize(space)?;
+    Ok(())
+}
+
+// Helper function to forfeit the bonds of an auction's unrevealed bids to the
+// requester agent. `bids` must hold exactly the unrevealed bids, sorted by key.
+fn forfeit_unrevealed_bids<'info>(
+    escrow: &Account<'info, EscrowAccount>,
+    requester_agent: &Account<'info, AgentAccount>,
+    bids: &'info [AccountInfo<'info>],
+) -> Result<u64> {
+    require!(
+        bids.len() == escrow.application_count as usize,
+        AgentLinkError::UnrevealedBidsMissing
+    );
+    let mut previous = Pubkey::default();
+    let mut forfeited: u64 = 0;
+    for info in bids.iter() {
+        let mut bid = Account::<Bid>::try_from(info)?;
+        require!(
+            bid.key() > previous && bid.bids_on(escrow.key(), escrow) && !bid.revealed,
+            AgentLinkError::UnrevealedBidsMissing
+        );
+        previous = bid.key();
+
+        if bid.bond > 0 {
+            **bid.to_account_info().try_borrow_mut_lamports()? -= bid.bond;
+            **requester_agent.to_account_info().try_borrow_mut_lamports()? += bid.bond;
+            forfeited = forfeited.checked_add(bid.bond).unwrap();
+            bid.bond = 0;
+            bid.exit(&crate::ID)?;
+        }
+    }
+    Ok(forfeited)
+}
+
+// Helper function to pay out of a token escrow vault, signed by the escrow PDA
+fn transfer_from_vault<'info>(
+    escrow: &Account<'info, EscrowAccount>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let bump = [escrow.bump];
+    let seeds = escrow.signer_seeds(&bump);
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: vault.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: escrow.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+        mint.decimals,
+    )
+}
+
+// Helper function to release a worker's bond held in the escrow, either back
+// to the worker agent or slashed to the requester agent
+fn settle_bond<'info>(
+    escrow: &mut Account<'info, EscrowAccount>,
+    recipient: &AccountInfo<'info>,
+    slashed: bool,
+) -> Result<()> {
+    let bond = escrow.bond;
+    if bond == 0 {
+        return Ok(());
+    }
+
+    **escrow.to_account_info().try_borrow_mut_lamports()? -= bond;
+    **recipient.try_borrow_mut_lamports()? += bond;
+    escrow.bond = 0;
+
+    emit!(BondSettled {
+        escrow: escrow.key(),
+        job_id: escrow.job_id.clone(),
+        recipient: recipient.key(),
+        amount: bond,
+        slashed,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!(
+        "Bond of {} lamports for job '{}' {}",
+        bond,
+        escrow.job_id,
+        if slashed { "slashed to requester" } else { "returned to worker" }
+    );
+
+    Ok(())
+}
+
+// Helper function to check that a signer may act for an agent, either as its
+// authority or through an unexpired session key scoped to the instruction.
+// Returns true when the signer is a session key.
+fn authorize_agent(
+    agent: &Account<AgentAccount>,
+    signer: Pubkey,
+    session: Option<&SessionKey>,
+    permission: u16,
+) -> Result<bool> {
+    if signer == agent.authority {
+        return Ok(false);
+    }
+
+    let session = session.ok_or(AgentLinkError::Unauthorized)?;
+    require!(
+        session.agent == agent.key()
+            && session.key == signer
+            && session.authority == agent.authority,
+        AgentLinkError::Unauthorized
+    );
+    require!(
+        Clock::get()?.unix_timestamp < session.expires_at,
+        AgentLinkError::SessionExpired
+    );
+    require!(
+        session.allowed_ixs & permission != 0,
+        AgentLinkError::SessionNotPermitted
+    );
+
+    Ok(true)
+}
+
+// Helper function to count lamports a session key moves against its spend cap
+fn charge_session(session: &mut SessionKey, amount: u64) -> Result<()> {
+    session.spent = session.spent.checked_add(amount).unwrap();
+    require!(
+        session.spent <= session.spend_cap,
+        AgentLinkError::SessionSpendCapExceeded
+    );
+    Ok(())
+}
+
+// Helper function to charge a session-signed payout against the session spend
+// cap. Escrows the session funded from the agent PDA were charged up front.
+fn charge_session_payout(
+    session: &mut Option<Account<SessionKey>>,
+    escrow: &EscrowAccount,
+    amount: u64,
+) -> Result<()> {
+    if escrow.funded_by_agent {
+        return Ok(());
+    }
+    let session = session.as_mut().ok_or(AgentLinkError::Unauthorized)?;
+    charge_session(session, amount)
+}
+
+// Helper function to fund a new escrow, either from the requester's wallet or,
+// for session keys, from the requester agent PDA within the session spend cap
+fn fund_escrow<'info>(
+    escrow: &Account<'info, EscrowAccount>,
+    requester_agent: &Account<'info, AgentAccount>,
+    requester: &Signer<'info>,
+    session: &mut Option<Account<'info, SessionKey>>,
+    system_program: &Program<'info, System>,
+    funded_by_agent: bool,
+    amount: u64,
+) -> Result<()> {
+    if !funded_by_agent {
+        return system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: requester.to_account_info(),
+                    to: escrow.to_account_info(),
+                },
+            ),
+            amount,
+        );
+    }
+
+    let session = session.as_mut().ok_or(AgentLinkError::Unauthorized)?;
+    charge_session(session, amount)?;
+
+    // Agent PDA must stay rent-exempt
+    let rent = Rent::get()?;
+    let min_balance = rent.minimum_balance(AgentAccount::SPACE);
+    let available = requester_agent
+        .to_account_info()
+        .lamports()
+        .saturating_sub(min_balance);
+    require!(amount <= available, AgentLinkError::InsufficientFunds);
+
+    **requester_agent.to_account_info().try_borrow_mut_lamports()? -= amount;
+    **escrow.to_account_info().try_borrow_mut_lamports()? += amount;
+
+    Ok(())
+}
+
+// Helper function to refund a native escrow to whoever funded it. Escrows
+// funded by a session key go back to the requester agent PDA; wallet-funded
+// escrows only refund to the requester's authority. Sub-job refunds stay in
+// the escrow until settle_subjob returns them to the parent.
+fn refund_requester<'info>(
+    escrow: &Account<'info, EscrowAccount>,
+    requester_agent: &Account<'info, AgentAccount>,
+    requester: &AccountInfo<'info>,
+    used_session: bool,
+    amount: u64,
+) -> Result<()> {
+    if escrow.parent != Pubkey::default() {
+        return Ok(());
+    }
+
+    if escrow.funded_by_agent {
+        **escrow.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **requester_agent.to_account_info().try_borrow_mut_lamports()? += amount;
+    } else {
+        require!(!used_session, AgentLinkError::SessionNotPermitted);
+        require!(
+            requester.key() == requester_agent.authority,
+            AgentLinkError::InvalidRequester
+        );
+        **escrow.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **requester.try_borrow_mut_lamports()? += amount;
+    }
+
+    Ok(())
+}
+
+// Helper function to pay a worker out of a native escrow. The protocol fee
+// goes to the treasury (none without one) and the rest is split between the
+// worker agent and its creator. Returns (protocol_fee, creator_amount,
+// worker_amount).
+fn payout_worker<'info>(
+    escrow: &AccountInfo<'info>,
+    worker_agent: &Account<'info, AgentAccount>,
+    worker_creator: &AccountInfo<'info>,
+    treasury: Option<&AccountInfo<'info>>,
+    amount: u64,
+    fee_bps: u16,
+) -> Result<(u64, u64, u64)> {
+    let protocol_fee = if treasury.is_some() { calculate_fee(amount, fee_bps) } else { 0 };
+    let payout = amount.checked_sub(protocol_fee).unwrap();
+    let (creator_amount, worker_amount) = calculate_split(payout, worker_agent.creator_split_bps);
+
+    if let Some(treasury) = treasury {
+        if protocol_fee > 0 {
+            **escrow.try_borrow_mut_lamports()? -= protocol_fee;
+            **treasury.try_borrow_mut_lamports()? += protocol_fee;
+        }
+    }
+
+    if creator_amount > 0 {
+        **escrow.try_borrow_mut_lamports()? -= creator_amount;
+        **worker_creator.try_borrow_mut_lamports()? += creator_amount;
+    }
+
+    if worker_amount > 0 {
+        **escrow.try_borrow_mut_lamports()? -= worker_amount;
+        **worker_agent.to_account_info().try_borrow_mut_lamports()? += worker_amount;
+    }
+
+    Ok((protocol_fee, creator_amount, worker_amount))
+}
+
+// Helper function to pay a worker out of a token escrow vault, the token
+// counterpart of `payout_worker`
+#[allow(clippy::too_many_arguments)]
+fn payout_worker_token<'info>(
+    escrow: &Account<'info, EscrowAccount>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    worker_agent: &Account<'info, AgentAccount>,
+    worker_token: &InterfaceAccount<'info, TokenAccount>,
+    worker_creator_token: &InterfaceAccount<'info, TokenAccount>,
+    treasury_token: Option<&InterfaceAccount<'info, TokenAccount>>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+    fee_bps: u16,
+) -> Result<(u64, u64, u64)> {
+    let protocol_fee = if treasury_token.is_some() { calculate_fee(amount, fee_bps) } else { 0 };
+    let payout = amount.checked_sub(protocol_fee).unwrap();
+    let (creator_amount, worker_amount) = calculate_split(payout, worker_agent.creator_split_bps);
+
+    if let Some(treasury_token) = treasury_token {
+        transfer_from_vault(escrow, vault, mint, treasury_token, token_program, protocol_fee)?;
+    }
+    transfer_from_vault(escrow, vault, mint, worker_creator_token, token_program, creator_amount)?;
+    transfer_from_vault(escrow, vault, mint, worker_token, token_program, worker_amount)?;
+
+    Ok((protocol_fee, creator_amount, worker_amount))
+}
+
+// Helper function to calculate the protocol fee on a payment
+fn calculate_fee(amount: u64, fee_bps: u16) -> u64 {
+    (amount as u128)
+        .checked_mul(fee_bps as u128)
+        .unwrap()
+        .checked_div(BPS_DENOMINATOR as u128)
+        .unwrap() as u64
+}
+
+// Helper function to split a payment between an agent and its creator.
+// Returns (creator_amount, worker_amount).
+fn calculate_split(amount: u64, split_bps: u16) -> (u64, u64) {
+    let creator_amount = (amount as u128)
+        .checked_mul(split_bps as u128)
+        .unwrap()
+        .checked_div(BPS_DENOMINATOR as u128)
+        .unwrap() as u64;
+    let worker_amount = amount.checked_sub(creator_amount).unwrap();
+    (creator_amount, worker_amount)
+}
+
+// Helper function to release a submitted milestone to the worker. Completes
+// the job once every milestone has been released.
+#[allow(clippy::too_many_arguments)]
+fn release_milestone<'info>(
+    escrow: &mut Account<'info, EscrowAccount>,
+    worker_agent: &mut Account<'info, AgentAccount>,
+    requester_agent: &mut Account<'info, AgentAccount>,
+    pair: &mut Account<'info, AgentPair>,
+    worker_creator: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    config: &ProtocolConfig,
+    index: u8,
+    timed_out: bool,
+) -> Result<()> {
+    let milestone = escrow
+        .milestones
+        .get(index as usize)
+        .ok_or(AgentLinkError::InvalidMilestone)?;
+    require!(
+        milestone.status == MILESTONE_SUBMITTED,
+        AgentLinkError::InvalidMilestoneStatus
+    );
+
+    // Do lamport transfers first
+    let milestone_amount = milestone.amount;
+    let (protocol_fee, creator_amount, worker_amount) = payout_worker(
+        &escrow.to_account_info(),
+        worker_agent,
+        worker_creator,
+        Some(treasury),
+        milestone_amount,
+        config.protocol_fee_bps,
+    )?;
+    let payout = milestone_amount.checked_sub(protocol_fee).unwrap();
+
+    // Now update account data
+    escrow.milestones[index as usize].status = MILESTONE_RELEASED;
+    escrow.amount = escrow.amount.checked_sub(milestone_amount).unwrap();
+
+    let all_released = escrow
+        .milestones
+        .iter()
+        .all(|m| m.status == MILESTONE_RELEASED);
+    if all_released {
+        escrow.status = STATUS_COMPLETED;
+        let job_value = escrow.milestones.iter().map(|m| m.amount).sum();
+        let weight_bps = record_pair_job(pair, job_value, &escrow.mint, config);
+        record_successful_job(worker_agent, payout, weight_bps, config.verification_threshold)?;
+        settle_bond(escrow, &worker_agent.to_account_info(), false)?;
+
+        let submitted_at = escrow.milestones[index as usize].submitted_at;
+        record_requester_payment(
+            requester_agent,
+            submitted_at,
+            Clock::get()?.unix_timestamp,
+            timed_out,
+        );
+    } else {
+        record_earnings(worker_agent, payout)?;
+    }
+
+    emit!(MilestoneReleased {
+        escrow: escrow.key(),
+        job_id: escrow.job_id.clone(),
+        requester: escrow.requester,
+        worker: escrow.worker,
+        index,
+        amount: milestone_amount,
+        protocol_fee,
+        worker_amount,
+        creator_amount,
+        job_completed: all_released,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!(
+        "Milestone {} of job '{}' released. Worker received {} lamports, creator received {} lamports",
+        index,
+        escrow.job_id,
+        worker_amount,
+        creator_amount
+    );
+
+    Ok(())
+}
+
+// Helper function to credit a worker for a successfully paid job
+fn record_successful_job(
+    worker_agent: &mut Account<AgentAccount>,
+    amount: u64,
+    weight_bps: u64,
+    verification_threshold: u32,
+) -> Result<()> {
+    worker_agent.successful_jobs = worker_agent.successful_jobs.checked_add(1).unwrap();
+    worker_agent.weighted_jobs_bps = worker_agent.weighted_jobs_bps.checked_add(weight_bps).unwrap();
+    worker_agent.weighted_earned = worker_agent
+        .weighted_earned
+        .checked_add(weighted_amount(amount, weight_bps))
+        .unwrap();
+    worker_agent.last_completed_at = Clock::get()?.unix_timestamp;
+
+    // Check for auto-verification (weighted jobs only)
+    let verified_jobs_bps = (verification_threshold as u64) * (BPS_DENOMINATOR as u64);
+    if worker_agent.weighted_jobs_bps >= verified_jobs_bps && !worker_agent.verified {
+        worker_agent.verified = true;
+        emit!(AgentVerified {
+            agent: worker_agent.key(),
+            successful_jobs: worker_agent.successful_jobs,
+        });
+        msg!("Agent '{}' is now verified!", worker_agent.name);
+    }
+
+    record_earnings(worker_agent, amount)
+}
+
+// Helper function to validate the terms of a service listing
+fn validate_service_terms(
+    terms: &ServiceTerms,
+    agent: &AgentAccount,
+    config: &ProtocolConfig,
+) -> Result<()> {
+    require!(terms.price > 0, AgentLinkError::InvalidAmount);
+    require!(
+        config.is_allowed_timeout(terms.timeout_hours),
+        AgentLinkError::InvalidTimeout
+    );
+    require!(
+        config.is_allowed_timeout(terms.review_hours),
+        AgentLinkError::InvalidReviewWindow
+    );
+    require!(
+        terms.max_queue > 0 && terms.capability & !agent.capabilities == 0,
+        AgentLinkError::InvalidServiceTerms
+    );
+    Ok(())
+}
+
+// Helper function to check an agent against a job's reputation gates, using
+// the reputation decayed to now rather than the last stored score
+fn passes_hire_gates(escrow: &EscrowAccount, agent: &AgentAccount, now: i64) -> bool {
+    agent.reputation_at(now) >= escrow.min_reputation
+        && agent.weighted_jobs_bps / (BPS_DENOMINATOR as u64) >= escrow.min_jobs as u64
+        && (agent.verified || !escrow.require_verified)
+}
+
+// Helper function to check whether an application can be hired automatically:
+// it passes the gates, holds the bond and is not a self-deal
+fn is_qualified_applicant(
+    escrow: &EscrowAccount,
+    requester_agent: &AgentAccount,
+    application: &Application,
+    worker_agent: &AgentAccount,
+    now: i64,
+) -> bool {
+    passes_hire_gates(escrow, worker_agent, now)
+        && application.bond >= escrow.required_bond
+        && requester_agent.creator != worker_agent.creator
+        && requester_agent.authority != worker_agent.authority
+}
+
+// Helper function to move the bond posted with an application into the escrow
+fn take_application_bond(
+    application: &mut Account<Application>,
+    escrow: &mut Account<EscrowAccount>,
+    bond: u64,
+) -> Result<()> {
+    require!(application.bond >= bond, AgentLinkError::BondNotPosted);
+    application.bond = application.bond.checked_sub(bond).unwrap();
+    **application.to_account_info().try_borrow_mut_lamports()? -= bond;
+    **escrow.to_account_info().try_borrow_mut_lamports()? += bond;
+    escrow.bond = bond;
+    Ok(())
+}
+
+// Helper function to assign the worker, start the delivery clock and count the
+// hire between the pair
+fn start_job(
+    escrow: &mut Account<EscrowAccount>,
+    pair: &mut Account<AgentPair>,
+    pair_bump: u8,
+    worker_agent: Pubkey,
+    now: i64,
+) -> Result<()> {
+    if pair.requester == Pubkey::default() {
+        pair.bump = pair_bump;
+        pair.requester = escrow.requester;
+        pair.worker = worker_agent;
+    }
+    pair.hires = pair.hires.checked_add(1).unwrap();
+
+    escrow.worker = worker_agent;
+    escrow.status = STATUS_IN_PROGRESS;
+    escrow.deadline = now + (escrow.timeout_hours as i64 * 3600);
+    if escrow.deadline_cap != 0 {
+        escrow.deadline = escrow.deadline.min(escrow.deadline_cap);
+    }
+    require!(escrow.deadline > now, AgentLinkError::DeadlinePassed);
+
+    emit!(AgentHired {
+        escrow: escrow.key(),
+        job_id: escrow.job_id.clone(),
+        requester: escrow.requester,
+        worker: escrow.worker,
+        deadline: escrow.deadline,
+        bond: escrow.bond,
+        timestamp: now,
+    });
+
+    msg!(
+        "Agent hired for job '{}'. Deadline: {}",
+        escrow.job_id,
+        escrow.deadline
+    );
+
+    Ok(())
+}
+
+// Helper function to count a completed job between a requester and worker and
+// return the reputation weight it carries. Jobs paid in a mint that is not on
+// the config allowlist carry no weight, since anyone can mint a token to pay
+// themselves.
+fn record_pair_job(
+    pair: &mut Account<AgentPair>,
+    job_value: u64,
+    mint: &Pubkey,
+    config: &ProtocolConfig,
+) -> u64 {
+    let weight_bps = match config.min_reputation_value(mint) {
+        Some(min_job_value) => job_weight_bps(job_value, min_job_value, pair.jobs_completed),
+        None => 0,
+    };
+    pair.jobs_completed = pair.jobs_completed.checked_add(1).unwrap();
+    weight_bps
+}
+
+// Helper function to calculate the reputation weight of a job (10000 = one
+// full job). Jobs below the minimum value count for nothing, and each repeat
+// deal between the same pair counts less: 1, 1/2, 1/3, ...
+fn job_weight_bps(job_value: u64, min_job_value: u64, previous_pair_jobs: u32) -> u64 {
+    if job_value < min_job_value {
+        return 0;
+    }
+    (BPS_DENOMINATOR as u64) / (previous_pair_jobs as u64 + 1)
+}
+
+// Helper function to scale a job's earnings by its reputation weight
+fn weighted_amount(amount: u64, weight_bps: u64) -> u64 {
+    ((amount as u128) * (weight_bps as u128) / (BPS_DENOMINATOR as u128)) as u64
+}
+
+// Helper function to credit a worker for lamports earned outside a full job
+fn record_earnings(worker_agent: &mut Account<AgentAccount>, amount: u64) -> Result<()> {
+    worker_agent.total_earned = worker_agent.total_earned.checked_add(amount).unwrap();
+
+    // Update reputation score
+    update_reputation(worker_agent, Clock::get()?.unix_timestamp);
+
+    Ok(())
+}
+
+// Helper function to record who lost an arbiter's ruling. A ruling below 50%
+// for the worker is a loss for the worker; above 50% is a loss for the requester.
+fn record_dispute_outcome<'info>(
+    worker_agent: &mut Account<'info, AgentAccount>,
+    requester_agent: &mut Account<'info, AgentAccount>,
+    worker_share_bps: u16,
+) -> Result<()> {
+    let loser = match worker_share_bps.cmp(&(BPS_DENOMINATOR / 2)) {
+        std::cmp::Ordering::Less => worker_agent,
+        std::cmp::Ordering::Greater => requester_agent,
+        std::cmp::Ordering::Equal => return Ok(()),
+    };
+
+    loser.disputes_lost = loser.disputes_lost.checked_add(1).unwrap();
+    update_reputation(loser, Clock::get()?.unix_timestamp);
+
+    Ok(())
+}
+
+// Helper function to recompute an agent's stored reputation score
+fn update_reputation(agent: &mut Account<AgentAccount>, now: i64) {
+    agent.reputation_score = agent.reputation_at(now);
+}
+
+// Helper function to record a payout on the requester side
+fn record_requester_payment(
+    requester_agent: &mut Account<AgentAccount>,
+    completed_at: i64,
+    now: i64,
+    timed_out: bool,
+) {
+    requester_agent.jobs_paid = requester_agent.jobs_paid.checked_add(1).unwrap();
+    if timed_out {
+        requester_agent.timeouts_suffered = requester_agent.timeouts_suffered.checked_add(1).unwrap();
+    } else {
+        let latency = now.saturating_sub(completed_at).max(0) as u64;
+        requester_agent.total_approval_latency =
+            requester_agent.total_approval_latency.checked_add(latency).unwrap();
+    }
+    refresh_requester_score(requester_agent);
+}
+
+// Helper function to recompute a requester's reliability score
+fn refresh_requester_score(requester_agent: &mut Account<AgentAccount>) {
+    requester_agent.requester_score = calculate_requester_score(
+        requester_agent.jobs_paid,
+        requester_agent.timeouts_suffered,
+        requester_agent.disputes_raised,
+        requester_agent.cancellations,
+        requester_agent.avg_approval_latency(),
+    );
+}
+
+// Helper function to calculate requester reliability (0-10000): the share of
+// jobs the requester approved itself rather than letting time out, disputing or
+// cancelling, reduced by up to 20% for slow approvals (72h+ average)
+fn calculate_requester_score(
+    jobs_paid: u32,
+    timeouts_suffered: u32,
+    disputes_raised: u32,
+    cancellations: u32,
+    avg_approval_latency: u64,
+) -> u16 {
+    let total = jobs_paid as u64 + disputes_raised as u64 + cancellations as u64;
+    if total == 0 {
+        return 0;
+    }
+
+    let approved = jobs_paid.saturating_sub(timeouts_suffered) as u64;
+    let score = approved * BPS_DENOMINATOR as u64 / total;
+
+    let latency_hours = (avg_approval_latency / 3600).min(72);
+    let penalty = score * latency_hours * 2000 / (72 * BPS_DENOMINATOR as u64);
+
+    (score - penalty) as u16
+}
+
+// Helper function to calculate reputation score (0-10000). Completed jobs and
+// their earnings, both scaled by job weight, add points; lost disputes and no-shows subtract them.
+// After 30 idle days since the last completed job the score decays by 1% a day,
+// down to half of its value.
+fn calculate_reputation(
+    weighted_jobs_bps: u64,
+    weighted_earned: u64,
+    disputes_lost: u32,
+    no_shows: u32,
+    last_completed_at: i64,
+    now: i64,
+) -> u16 {
+    let job_points = weighted_jobs_bps.saturating_mul(500) / (BPS_DENOMINATOR as u64);
+    let earned_points = (weighted_earned / LAMPORTS_PER_SOL).saturating_mul(10);
+    let positive = job_points.saturating_add(earned_points);
+
+    let dispute_penalty = (disputes_lost as u64).saturating_mul(1000);
+    let no_show_penalty = (no_shows as u64).saturating_mul(750);
+    let negative = dispute_penalty.saturating_add(no_show_penalty);
+
+    let score = std::cmp::min(positive.saturating_sub(negative), 10000);
+
+    // Decay with inactivity since the last completed job
+    if last_completed_at == 0 {
+        return score as u16;
+    }
+    let idle_days = (now.saturating_sub(last_completed_at).max(0) / 86400) as u64;
+    let decay_bps = std::cmp::min(idle_days.saturating_sub(30).saturating_mul(100), 5000);
+
+    (score * (10000 - decay_bps) / 10000) as u16
+}
+
+// ============== ACCOUNTS ==============
+
+#[account]
+pub struct AgentAccount {
+    pub bump: u8,                    // 1
+    pub name: String,                // 4 + MAX_NAME_LENGTH
+    pub creator: Pubkey,             // 32
+    pub authority: Pubkey,           // 32
+    pub created_at: i64,             // 8
+    pub creator_signed: bool,        // 1
+    pub verified: bool,              // 1
+    pub successful_jobs: u32,        // 4
+    pub total_earned: u64,           // 8
+    pub total_spent: u64,            // 8
+    pub reputation_score: u16,       // 2
+    pub creator_split_bps: u16,      // 2
+    pub no_shows: u32,               // 4
+    pub pending_authority: Pubkey,   // 32 (default = none)
+    pub metadata_uri: String,        // 4 + MAX_METADATA_URI_LENGTH
+    pub metadata_hash: [u8; 32],     // 32 (hash of the metadata document)
+    pub capabilities: u64,           // 8 (capability bitmap)
+    pub jobs_paid: u32,              // 4 (as requester, approved or timed out)
+    pub timeouts_suffered: u32,      // 4 (as requester, paid by timeout)
+    pub disputes_raised: u32,        // 4 (as requester)
+    pub cancellations: u32,          // 4 (as requester)
+    pub total_approval_latency: u64, // 8 (seconds from completion to approval)
+    pub requester_score: u16,        // 2 (0-10000 reliability as requester)
+    pub disputes_lost: u32,          // 4
+    pub last_completed_at: i64,      // 8 (last successful job, 0 = never)
+    pub weighted_jobs_bps: u64,      // 8 (jobs counted for reputation, 10000 = one job)
+    pub weighted_earned: u64,        // 8 (lamports earned, scaled by job weight)
+}
+
+impl AgentAccount {
+    pub const SPACE: usize = 8 // discriminator
+        + 1  // bump
+        + 4 + MAX_NAME_LENGTH  // name
+        + 32 // creator
+        + 32 // authority
+        + 8  // created_at
+        + 1  // creator_signed
+        + 1  // verified
+        + 4  // successful_jobs
+        + 8  // total_earned
+        + 8  // total_spent
+        + 2  // reputation_score
+        + 2  // creator_split_bps
+        + 4  // no_shows
+        + 32 // pending_authority
+        + 4 + MAX_METADATA_URI_LENGTH // metadata_uri
+        + 32 // metadata_hash
+        + 8  // capabilities
+        + 4  // jobs_paid
+        + 4  // timeouts_suffered
+        + 4  // disputes_raised
+        + 4  // cancellations
+        + 8  // total_approval_latency
+        + 2  // requester_score
+        + 4  // disputes_lost
+        + 8  // last_completed_at
+        + 8  // weighted_jobs_bps
+        + 8; // weighted_earned
+
+    /// Reputation score as of `now`, including inactivity decay that the
+    /// stored `reputation_score` only picks up when it is recomputed
+    pub fn reputation_at(&self, now: i64) -> u16 {
+        calculate_reputation(
+            self.weighted_jobs_bps,
+            self.weighted_earned,
+            self.disputes_lost,
+            self.no_shows,
+            self.last_completed_at,
+            now,
+        )
+    }
+
+    /// Average seconds between completion and approval, over approved jobs
+    pub fn avg_approval_latency(&self) -> u64 {
+        let approved = self.jobs_paid.saturating_sub(self.timeouts_suffered) as u64;
+        if approved == 0 {
+            return 0;
+        }
+        self.total_approval_latency / approved
+    }
+}
+
+#[account]
+pub struct EscrowAccount {
+    pub bump: u8,                    // 1
+    pub job_id: String,              // 4 + MAX_JOB_ID_LENGTH
+    pub job_hash: [u8; 32],          // 32
+    pub requester: Pubkey,           // 32
+    pub worker: Pubkey,              // 32
+    pub amount: u64,                 // 8
+    pub status: u8,                  // 1
+    pub timeout_hours: u8,           // 1 (delivery window from hire)
+    pub review_hours: u8,            // 1 (review window from completion)
+    pub deadline: i64,               // 8 (delivery deadline)
+    pub completed_at: i64,           // 8
+    pub created_at: i64,             // 8
+    pub arbiter: Pubkey,             // 32
+    pub payer: Pubkey,               // 32 (rent payer, refunded on close)
+    pub mint: Pubkey,                // 32 (default = native SOL)
+    pub funded_by_agent: bool,       // 1 (escrow drawn from requester agent PDA)
+    pub required_bond: u64,          // 8 (lamports the worker must post at hire)
+    pub bond: u64,                   // 8 (worker bond currently held)
+    pub result_hash: [u8; 32],       // 32 (deliverable committed at completion)
+    pub result_uri: String,          // 4 + MAX_RESULT_URI_LENGTH
+    pub key_commitment: [u8; 32],    // 32 (sha256 of decryption key, zero = none)
+    pub decryption_key: [u8; 32],    // 32 (set on reveal)
+    pub key_revealed: bool,          // 1
+    pub approved_at: i64,            // 8 (requester approval awaiting key)
+    pub hire_mode: u8,               // 1 (HIRE_MODE_*)
+    pub min_reputation: u16,         // 2
+    pub require_verified: bool,      // 1
+    pub min_jobs: u32,               // 4
+    pub hire_after: i64,             // 8 (best_after hire window close)
+    pub application_count: u32,      // 4 (open applications, or unrevealed bids for auctions)
+    pub reveal_deadline: i64,        // 8 (auction reveal window close)
+    pub best_bid: u64,               // 8 (lowest revealed auction bid)
+    pub best_bidder: Pubkey,         // 32
+    pub service: Pubkey,             // 32 (listing holding a queue slot, default = none)
+    pub parent: Pubkey,              // 32 (parent escrow of a sub-job, default = none)
+    pub parent_open: bool,           // 1 (still counted in the parent's open_subjobs)
+    pub open_subjobs: u16,           // 2
+    pub subjob_total: u64,           // 8 (lamports carved into sub-jobs)
+    pub deadline_cap: i64,           // 8 (latest delivery deadline, 0 = none)
+    pub seed_version: u8,            // 1 (ESCROW_SEEDS_*)
+    pub prerequisites: Vec<Pubkey>,  // 4 + MAX_PREREQUISITES * 32
+    pub met_prerequisites: u8,       // 1 (bitmask over prerequisites)
+    pub pipeline: Pubkey,            // 32 (funding pipeline, default = none)
+    pub milestones: Vec<Milestone>,  // 4 + MAX_MILESTONES * Milestone::SPACE
+}
+
+impl EscrowAccount {
+    pub const SPACE: usize = 8 // discriminator
+        + 1  // bump
+        + 4 + MAX_JOB_ID_LENGTH  // job_id
+        + 32 // job_hash
+        + 32 // requester
+        + 32 // worker
+        + 8  // amount
+        + 1  // status
+        + 1  // timeout_hours
+        + 1  // review_hours
+        + 8  // deadline
+        + 8  // completed_at
+        + 8  // created_at
+        + 32 // arbiter
+        + 32 // payer
+        + 32 // mint
+        + 1  // funded_by_agent
+        + 8  // required_bond
+        + 8  // bond
+        + 32 // result_hash
+        + 4 + MAX_RESULT_URI_LENGTH // result_uri
+        + 32 // key_commitment
+        + 32 // decryption_key
+        + 1  // key_revealed
+        + 8  // approved_at
+        + 1  // hire_mode
+        + 2  // min_reputation
+        + 1  // require_verified
+        + 4  // min_jobs
+        + 8  // hire_after
+        + 4  // application_count
+        + 8  // reveal_deadline
+        + 8  // best_bid
+        + 32 // best_bidder
+        + 32 // service
+        + 32 // parent
+        + 1  // parent_open
+        + 2  // open_subjobs
+        + 8  // subjob_total
+        + 8  // deadline_cap
+        + 1  // seed_version
+        + 4 + MAX_PREREQUISITES * 32 // prerequisites
+        + 1  // met_prerequisites
+        + 32 // pipeline
+        + 4 + MAX_MILESTONES * Milestone::SPACE; // milestones
+
+    /// Set every field of a newly created escrow to its starting state: an
+    /// open, unbonded, manually hired native job. Creation paths override
+    /// whatever they add on top.
+    #[allow(clippy::too_many_arguments)]
+    pub fn init(
+        &mut self,
+        bump: u8,
+        job_id: String,
+        job_hash: [u8; 32],
+        requester: Pubkey,
+        payer: Pubkey,
+        amount: u64,
+        timeout_hours: u8,
+        review_hours: u8,
+        created_at: i64,
+    ) {
+        self.bump = bump;
+        self.job_id = job_id;
+        self.job_hash = job_hash;
+        self.requester = requester;
+        self.worker = Pubkey::default();
+        self.amount = amount;
+        self.status = STATUS_OPEN;
+        self.timeout_hours = timeout_hours;
+        self.review_hours = review_hours;
+        self.deadline = 0; // Set when hired
+        self.completed_at = 0; // Set when worker completes
+        self.created_at = created_at;
+        self.arbiter = Pubkey::default();
+        self.payer = payer;
+        self.mint = Pubkey::default();
+        self.funded_by_agent = false;
+        self.required_bond = 0;
+        self.bond = 0;
+        self.result_hash = [0u8; 32];
+        self.result_uri = String::new();
+        self.key_commitment = [0u8; 32];
+        self.decryption_key = [0u8; 32];
+        self.key_revealed = false;
+        self.approved_at = 0;
+        self.set_hire_gates(&HireGates::default());
+        self.service = Pubkey::default();
+        self.parent = Pubkey::default();
+        self.parent_open = false;
+        self.open_subjobs = 0;
+        self.subjob_total = 0;
+        self.deadline_cap = 0;
+        self.seed_version = ESCROW_SEEDS_REQUESTER;
+        self.prerequisites = Vec::new();
+        self.met_prerequisites = 0;
+        self.pipeline = Pubkey::default();
+        self.milestones = Vec::new();
+    }
+
+    /// Whether this escrow holds native SOL rather than SPL tokens
+    pub fn is_native(&self) -> bool {
+        self.mint == Pubkey::default()
+    }
+
+    /// Whether the escrow has been fully settled and can be closed
+    pub fn is_terminal(&self) -> bool {
+        self.status == STATUS_COMPLETED
+            || self.status == STATUS_CANCELLED
+            || self.status == STATUS_RESOLVED
+    }
+
+    /// When the requester's review window closes after completion
+    pub fn review_deadline(&self) -> i64 {
+        self.completed_at + (self.review_hours as i64 * 3600)
+    }
+
+    /// Whether every prerequisite escrow has been recorded as completed
+    pub fn prerequisites_met(&self) -> bool {
+        self.met_prerequisites.count_ones() as usize == self.prerequisites.len()
+    }
+
+    /// Store the hire mode and reputation gates set at creation
+    pub fn set_hire_gates(&mut self, gates: &HireGates) {
+        self.hire_mode = gates.hire_mode;
+        self.min_reputation = gates.min_reputation;
+        self.require_verified = gates.require_verified;
+        self.min_jobs = gates.min_jobs;
+        self.hire_after = self.created_at + (gates.hire_window_hours as i64 * 3600);
+        self.application_count = 0;
+        self.reveal_deadline = self.hire_after + (gates.reveal_window_hours as i64 * 3600);
+        self.best_bid = 0;
+        self.best_bidder = Pubkey::default();
+    }
+
+    /// PDA signer seeds for the layout this escrow was created under
+    pub fn signer_seeds<'a>(&'a self, bump: &'a [u8; 1]) -> Vec<&'a [u8]> {
+        if self.seed_version == ESCROW_SEEDS_LEGACY {
+            vec![b"escrow", self.job_id.as_bytes(), bump]
+        } else {
+            vec![b"escrow", self.requester.as_ref(), self.job_id.as_bytes(), bump]
+        }
+    }
+
+    /// Whether the worker committed to a decryption key at completion
+    pub fn has_key_commitment(&self) -> bool {
+        self.key_commitment != [0u8; 32]
+    }
+
+    /// When an approved job's key must be revealed before it can be disputed
+    pub fn key_reveal_deadline(&self) -> i64 {
+        self.approved_at + (self.review_hours as i64 * 3600)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Milestone {
+    pub amount: u64,                 // 8
+    pub milestone_hash: [u8; 32],    // 32
+    pub status: u8,                  // 1
+    pub submitted_at: i64,           // 8
+}
+
+impl Milestone {
+    pub const SPACE: usize = 8 // amount
+        + 32 // milestone_hash
+        + 1  // status
+        + 8; // submitted_at
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MilestoneInput {
+    pub amount: u64,
+    pub milestone_hash: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct HireGates {
+    pub hire_mode: u8,
+    pub min_reputation: u16,
+    pub require_verified: bool,
+    pub min_jobs: u32,
+    pub hire_window_hours: u16,   // best_after hire window, or auction commit window
+    pub reveal_window_hours: u16, // auction reveal window
+}
+
+#[account]
+pub struct SessionKey {
+    pub bump: u8,                    // 1
+    pub agent: Pubkey,               // 32
+    pub authority: Pubkey,           // 32 (invalid once the agent authority rotates)
+    pub key: Pubkey,                 // 32
+    pub expires_at: i64,             // 8
+    pub allowed_ixs: u16,            // 2 (SESSION_* bitmask)
+    pub spend_cap: u64,              // 8 (lamports the session may fund or release)
+    pub spent: u64,                  // 8
+    pub created_at: i64,             // 8
+}
+
+impl SessionKey {
+    pub const SPACE: usize = 8 // discriminator
+        + 1  // bump
+        + 32 // agent
+        + 32 // authority
+        + 32 // key
+        + 8  // expires_at
+        + 2  // allowed_ixs
+        + 8  // spend_cap
+        + 8  // spent
+        + 8; // created_at
+}
+
+#[account]
+pub struct ProtocolConfig {
+    pub bump: u8,                    // 1
+    pub admin: Pubkey,               // 32
+    pub treasury: Pubkey,            // 32
+    pub max_split_bps: u16,          // 2
+    pub default_split_bps: u16,      // 2
+    pub verification_threshold: u32, // 4
+    pub allowed_timeouts: [u8; MAX_ALLOWED_TIMEOUTS], // 4 (hours, 0 = unused)
+    pub protocol_fee_bps: u16,       // 2
+    pub paused: bool,                // 1
+    pub min_reputation_job_value: u64, // 8 (lamports for a job to count toward reputation)
+    pub reputation_mints: Vec<ReputationMint>, // 4 + MAX_REPUTATION_MINTS * ReputationMint::SPACE
+}
+
+impl ProtocolConfig {
+    pub const SPACE: usize = 8 // discriminator
+        + 1  // bump
+        + 32 // admin
+        + 32 // treasury
+        + 2  // max_split_bps
+        + 2  // default_split_bps
+        + 4  // verification_threshold
+        + MAX_ALLOWED_TIMEOUTS // allowed_timeouts
+        + 2  // protocol_fee_bps
+        + 1  // paused
+        + 8  // min_reputation_job_value
+        + 4 + MAX_REPUTATION_MINTS * ReputationMint::SPACE; // reputation_mints
+
+    /// Whether a delivery or review window (in hours) is allowed
+    pub fn is_allowed_timeout(&self, hours: u8) -> bool {
+        hours > 0 && self.allowed_timeouts.contains(&hours)
+    }
+
+    /// Minimum job value for a job paid in `mint` to count toward reputation,
+    /// or None when the mint is not on the allowlist
+    pub fn min_reputation_value(&self, mint: &Pubkey) -> Option<u64> {
+        if *mint == Pubkey::default() {
+            return Some(self.min_reputation_job_value);
+        }
+        self.reputation_mints
+            .iter()
+            .find(|m| m.mint == *mint)
+            .map(|m| m.min_job_value)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ReputationMint {
+    pub mint: Pubkey,                // 32
+    pub min_job_value: u64,          // 8 (base units for a job to count toward reputation)
+}
+
+impl ReputationMint {
+    pub const SPACE: usize = 32 // mint
+        + 8; // min_job_value
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ConfigParams {
+    pub max_split_bps: u16,
+    pub default_split_bps: u16,
+    pub verification_threshold: u32,
+    pub allowed_timeouts: [u8; MAX_ALLOWED_TIMEOUTS],
+    pub protocol_fee_bps: u16,
+    pub min_reputation_job_value: u64,
+    pub reputation_mints: Vec<ReputationMint>,
+}
+
+#[account]
+pub struct Treasury {
+    pub bump: u8,                    // 1
+}
+
+impl Treasury {
+    pub const SPACE: usize = 8 // discriminator
+        + 1; // bump
+}
+
+#[account]
+pub struct AgentPair {
+    pub bump: u8,                    // 1
+    pub requester: Pubkey,           // 32 (requester agent)
+    pub worker: Pubkey,              // 32 (worker agent)
+    pub hires: u32,                  // 4
+    pub jobs_completed: u32,         // 4
+}
+
+impl AgentPair {
+    pub const SPACE: usize = 8 // discriminator
+        + 1  // bump
+        + 32 // requester
+        + 32 // worker
+        + 4  // hires
+        + 4; // jobs_completed
+}
+
+#[account]
+pub struct Application {
+    pub bump: u8,                    // 1
+    pub escrow: Pubkey,              // 32
+    pub escrow_created_at: i64,      // 8 (tells a recreated escrow apart)
+    pub agent: Pubkey,               // 32
+    pub applied_at: i64,             // 8
+    pub bond: u64,                   // 8 (bond lamports held until hire)
+}
+
+impl Application {
+    pub const SPACE: usize = 8 // discriminator
+        + 1  // bump
+        + 32 // escrow
+        + 8  // escrow_created_at
+        + 32 // agent
+        + 8  // applied_at
+        + 8; // bond
+
+    /// Whether this application was made to this instance of the escrow, not
+    /// to an earlier escrow closed under the same job ID
+    pub fn applies_to(&self, escrow_key: Pubkey, escrow: &EscrowAccount) -> bool {
+        self.escrow == escrow_key && self.escrow_created_at == escrow.created_at
+    }
+}
+
+#[account]
+pub struct Bid {
+    pub bump: u8,                    // 1
+    pub escrow: Pubkey,              // 32
+    pub escrow_created_at: i64,      // 8 (tells a recreated escrow apart)
+    pub agent: Pubkey,               // 32
+    pub commitment: [u8; 32],        // 32 (sha256 of amount LE bytes || salt)
+    pub amount: u64,                 // 8 (set on reveal)
+    pub revealed: bool,              // 1
+    pub committed_at: i64,           // 8
+    pub bond: u64,                   // 8 (bond lamports held until settlement)
+}
+
+impl Bid {
+    pub const SPACE: usize = 8 // discriminator
+        + 1  // bump
+        + 32 // escrow
+        + 8  // escrow_created_at
+        + 32 // agent
+        + 32 // commitment
+        + 8  // amount
+        + 1  // revealed
+        + 8  // committed_at
+        + 8; // bond
+
+    /// Whether this bid was committed to this instance of the escrow, not to
+    /// an earlier escrow closed under the same job ID
+    pub fn bids_on(&self, escrow_key: Pubkey, escrow: &EscrowAccount) -> bool {
+        self.escrow == escrow_key && self.escrow_created_at == escrow.created_at
+    }
+}
+
+#[account]
+pub struct ServiceListing {
+    pub bump: u8,                    // 1
+    pub agent: Pubkey,               // 32
+    pub service_id: String,          // 4 + MAX_SERVICE_ID_LENGTH
+    pub price: u64,                  // 8 (lamports per purchase)
+    pub capability: u64,             // 8 (capability bits this service covers)
+    pub terms_hash: [u8; 32],        // 32
+    pub timeout_hours: u8,           // 1 (delivery window from purchase)
+    pub review_hours: u8,            // 1
+    pub max_queue: u16,              // 2
+    pub queue_length: u16,           // 2 (purchased jobs holding a slot)
+    pub active: bool,                // 1
+    pub total_sales: u32,            // 4
+    pub created_at: i64,             // 8
+}
+
+impl ServiceListing {
+    pub const SPACE: usize = 8 // discriminator
+        + 1  // bump
+        + 32 // agent
+        + 4 + MAX_SERVICE_ID_LENGTH // service_id
+        + 8  // price
+        + 8  // capability
+        + 32 // terms_hash
+        + 1  // timeout_hours
+        + 1  // review_hours
+        + 2  // max_queue
+        + 2  // queue_length
+        + 1  // active
+        + 4  // total_sales
+        + 8; // created_at
+
+    /// Apply the worker's price, capability, timing and queue terms
+    pub fn set_terms(&mut self, terms: &ServiceTerms) {
+        self.price = terms.price;
+        self.capability = terms.capability;
+        self.terms_hash = terms.terms_hash;
+        self.timeout_hours = terms.timeout_hours;
+        self.review_hours = terms.review_hours;
+        self.max_queue = terms.max_queue;
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ServiceTerms {
+    pub price: u64,
+    pub capability: u64,
+    pub terms_hash: [u8; 32],
+    pub timeout_hours: u8,
+    pub review_hours: u8,
+    pub max_queue: u16,
+}
+
+#[account]
+pub struct Pipeline {
+    pub bump: u8,                    // 1
+    pub requester: Pubkey,           // 32 (requester agent)
+    pub pipeline_id: String,         // 4 + MAX_JOB_ID_LENGTH
+    pub total: u64,                  // 8 (lamports funded up front)
+    pub launched: u64,               // 8 (lamports moved into stage escrows)
+    pub created_at: i64,             // 8
+    pub stages: Vec<PipelineStage>,  // 4 + MAX_PIPELINE_STAGES * PipelineStage::SPACE
+}
+
+impl Pipeline {
+    pub const SPACE: usize = 8 // discriminator
+        + 1  // bump
+        + 32 // requester
+        + 4 + MAX_JOB_ID_LENGTH // pipeline_id
+        + 8  // total
+        + 8  // launched
+        + 8  // created_at
+        + 4 + MAX_PIPELINE_STAGES * PipelineStage::SPACE; // stages
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PipelineStage {
+    pub amount: u64,                 // 8
+    pub job_hash: [u8; 32],          // 32
+    pub escrow: Pubkey,              // 32 (default until launched)
+}
+
+impl PipelineStage {
+    pub const SPACE: usize = 8 // amount
+        + 32 // job_hash
+        + 32; // escrow
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PipelineStageInput {
+    pub amount: u64,
+    pub job_hash: [u8; 32],
+}
+
+#[account]
+pub struct Arbiter {
+    pub bump: u8,                    // 1
+    pub authority: Pubkey,           // 32
+    pub registered_by: Pubkey,       // 32
+    pub active: bool,                // 1
+    pub disputes_resolved: u32,      // 4
+    pub created_at: i64,             // 8
+}
+
+impl Arbiter {
+    pub const SPACE: usize = 8 // discriminator
+        + 1  // bump
+        + 32 // authority
+        + 32 // registered_by
+        + 1  // active
+        + 4  // disputes_resolved
+        + 8; // created_at
+}
+
+/// Agent account layout written by the original program, read by migrate_agent
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyAgentAccount {
+    pub bump: u8,                    // 1
+    pub name: String,                // 4 + MAX_NAME_LENGTH
+    pub creator: Pubkey,             // 32
+    pub authority: Pubkey,           // 32
+    pub created_at: i64,             // 8
+    pub creator_signed: bool,        // 1
+    pub verified: bool,              // 1
+    pub successful_jobs: u32,        // 4
+    pub total_earned: u64,           // 8
+    pub total_spent: u64,            // 8
+    pub reputation_score: u16,       // 2
+    pub creator_split_bps: u16,      // 2
+}
+
+impl LegacyAgentAccount {
+    pub const SPACE: usize = 8 // discriminator
+        + 1  // bump
+        + 4 + MAX_NAME_LENGTH  // name
+        + 32 // creator
+        + 32 // authority
+        + 8  // created_at
+        + 1  // creator_signed
+        + 1  // verified
+        + 4  // successful_jobs
+        + 8  // total_earned
+        + 8  // total_spent
+        + 2  // reputation_score
+        + 2; // creator_split_bps
+}
+
+/// Escrow layout written by the original program, read by migrate_escrow
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyEscrowAccount {
+    pub bump: u8,                    // 1
+    pub job_id: String,              // 4 + MAX_JOB_ID_LENGTH
+    pub job_hash: [u8; 32],          // 32
+    pub requester: Pubkey,           // 32
+    pub worker: Pubkey,              // 32
+    pub amount: u64,                 // 8
+    pub status: u8,                  // 1
+    pub timeout_hours: u8,           // 1
+    pub deadline: i64,               // 8
+    pub created_at: i64,             // 8
+}
+
+impl LegacyEscrowAccount {
+    pub const SPACE: usize = 8 // discriminator
+        + 1  // bump
+        + 4 + MAX_JOB_ID_LENGTH  // job_id
+        + 32 // job_hash
+        + 32 // requester
+        + 32 // worker
+        + 8  // amount
+        + 1  // status
+        + 1  // timeout_hours
+        + 8  // deadline
+        + 8; // created_at
+}
+
+// ============== CONTEXTS ==============
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RegisterAgent<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = AgentAccount::SPACE,
+        seeds = [b"agent", creator.key().as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub agent: Account<'info, AgentAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(job_id: String)]
+pub struct CreateJob<'info> {
+    #[account(mut)]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    #[account(
+        init,
+        payer = requester,
+        space = EscrowAccount::SPACE,
+        seeds = [b"escrow", requester_agent.key().as_ref(), job_id.as_bytes()],
+        bump
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// Session key signing for the requester agent (omit when its authority signs)
+    #[account(mut)]
+    pub session: Option<Account<'info, SessionKey>>,
+
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyToJob<'info> {
+    #[account(
+        init,
+        payer = worker,
+        space = Application::SPACE,
+        seeds = [b"application", escrow.key().as_ref(), worker_agent.key().as_ref()],
+        bump
+    )]
+    pub application: Account<'info, Application>,
+
+    #[account(mut)]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// Session key signing for the worker agent (omit when its authority signs)
+    pub session: Option<Account<'info, SessionKey>>,
+
+    #[account(mut)]
+    pub worker: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawApplication<'info> {
+    #[account(
+        mut,
+        close = worker,
+        constraint = application.agent == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
+    pub application: Account<'info, Application>,
+
+    /// CHECK: The application's escrow, which may already be closed
+    #[account(
+        mut,
+        address = application.escrow @ AgentLinkError::InvalidApplication
+    )]
+    pub escrow: AccountInfo<'info>,
+
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// Session key signing for the worker agent (omit when its authority signs)
+    pub session: Option<Account<'info, SessionKey>>,
+
+    #[account(mut)]
+    pub worker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RejectApplication<'info> {
+    #[account(
+        mut,
+        close = worker,
+        constraint = application.applies_to(escrow.key(), &escrow) @ AgentLinkError::InvalidApplication,
+        constraint = application.agent == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
+    pub application: Account<'info, Application>,
+
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// CHECK: Applicant's authority, receives the application's rent and bond
+    #[account(
+        mut,
+        address = worker_agent.authority @ AgentLinkError::InvalidWorker
+    )]
+    pub worker: AccountInfo<'info>,
+
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    /// Session key signing for the requester agent (omit when its authority signs)
+    pub session: Option<Account<'info, SessionKey>>,
+
+    pub requester: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct HireAgent<'info> {
+    #[account(mut)]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// Worker's application for this job (or pass the worker signer instead)
+    #[account(mut)]
+    pub application: Option<Account<'info, Application>>,
+
+    /// Worker's authority co-signing the hire (or pass an application instead)
+    #[account(mut)]
+    pub worker: Option<Signer<'info>>,
+
+    #[account(
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// Session key signing for the requester agent (omit when its authority signs)
+    pub session: Option<Account<'info, SessionKey>>,
+
+    #[account(
+        init_if_needed,
+        payer = requester,
+        space = AgentPair::SPACE,
+        seeds = [b"pair", requester_agent.key().as_ref(), worker_agent.key().as_ref()],
+        bump
+    )]
+    pub pair: Account<'info, AgentPair>,
+
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AutoHire<'info> {
+    #[account(
+        mut,
+        constraint = escrow.is_native() @ AgentLinkError::TokenEscrow
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    #[account(
+        mut,
+        constraint = application.applies_to(escrow.key(), &escrow)
+            && application.agent == worker_agent.key() @ AgentLinkError::InvalidApplication
+    )]
+    pub application: Account<'info, Application>,
+
+    #[account(
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = AgentPair::SPACE,
+        seeds = [b"pair", requester_agent.key().as_ref(), worker_agent.key().as_ref()],
+        bump
+    )]
+    pub pair: Account<'info, AgentPair>,
+
+    /// Anyone can trigger an automatic hire and pays for the pair account
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CommitBid<'info> {
+    #[account(
+        init,
+        payer = worker,
+        space = Bid::SPACE,
+        seeds = [b"bid", escrow.key().as_ref(), worker_agent.key().as_ref()],
+        bump
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        mut,
+        constraint = escrow.is_native() @ AgentLinkError::TokenEscrow
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    #[account(
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    /// Session key signing for the worker agent (omit when its authority signs)
+    pub session: Option<Account<'info, SessionKey>>,
+
+    #[account(mut)]
+    pub worker: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevealBid<'info> {
+    #[account(
+        mut,
+        seeds = [b"bid", escrow.key().as_ref(), worker_agent.key().as_ref()],
+        bump = bid.bump,
+        constraint = bid.bids_on(escrow.key(), &escrow) @ AgentLinkError::InvalidBid
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(mut)]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// Session key signing for the worker agent (omit when its authority signs)
+    pub session: Option<Account<'info, SessionKey>>,
+
+    pub worker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+    #[account(
+        mut,
+        constraint = escrow.is_native() @ AgentLinkError::TokenEscrow
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        constraint = escrow.best_bidder == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"bid", escrow.key().as_ref(), worker_agent.key().as_ref()],
+        bump = bid.bump,
+        constraint = bid.bids_on(escrow.key(), &escrow) @ AgentLinkError::InvalidBid
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    /// CHECK: Requester's authority wallet, refunded the excess unless the
+    /// escrow was funded from the agent PDA
+    #[account(
+        mut,
+        constraint = requester.key() == requester_agent.authority @ AgentLinkError::InvalidRequester
+    )]
+    pub requester: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = AgentPair::SPACE,
+        seeds = [b"pair", requester_agent.key().as_ref(), worker_agent.key().as_ref()],
+        bump
+    )]
+    pub pair: Account<'info, AgentPair>,
+
+    /// Anyone can settle a finished auction and pays for the pair account
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseFailedAuction<'info> {
+    #[account(mut)]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawBid<'info> {
+    #[account(
+        mut,
+        close = worker,
+        constraint = bid.agent == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
+    pub bid: Account<'info, Bid>,
+
+    /// CHECK: The bid's escrow, which may already be closed
+    #[account(
+        mut,
+        address = bid.escrow @ AgentLinkError::InvalidBid
+    )]
+    pub escrow: AccountInfo<'info>,
+
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// Session key signing for the worker agent (omit when its authority signs)
+    pub session: Option<Account<'info, SessionKey>>,
+
+    #[account(mut)]
+    pub worker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(service_id: String)]
+pub struct CreateServiceListing<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = ServiceListing::SPACE,
+        seeds = [b"service", agent.key().as_ref(), service_id.as_bytes()],
+        bump
+    )]
+    pub listing: Account<'info, ServiceListing>,
+
+    #[account(
+        constraint = authority.key() == agent.authority @ AgentLinkError::Unauthorized
+    )]
+    pub agent: Account<'info, AgentAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateServiceListing<'info> {
+    #[account(
+        mut,
+        constraint = listing.agent == agent.key() @ AgentLinkError::Unauthorized
+    )]
+    pub listing: Account<'info, ServiceListing>,
+
+    #[account(
+        constraint = authority.key() == agent.authority @ AgentLinkError::Unauthorized
+    )]
+    pub agent: Account<'info, AgentAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(job_id: String)]
+pub struct PurchaseService<'info> {
+    #[account(mut)]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    #[account(
+        init,
+        payer = requester,
+        space = EscrowAccount::SPACE,
+        seeds = [b"escrow", requester_agent.key().as_ref(), job_id.as_bytes()],
+        bump
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        constraint = listing.agent == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
+    pub listing: Account<'info, ServiceListing>,
+
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = requester,
+        space = AgentPair::SPACE,
+        seeds = [b"pair", requester_agent.key().as_ref(), worker_agent.key().as_ref()],
+        bump
+    )]
+    pub pair: Account<'info, AgentPair>,
+
+    /// Session key signing for the requester agent (omit when its authority signs)
+    #[account(mut)]
+    pub session: Option<Account<'info, SessionKey>>,
+
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseServiceSlot<'info> {
+    #[account(mut)]
+    pub listing: Account<'info, ServiceListing>,
+
+    #[account(
+        mut,
+        constraint = escrow.service == listing.key() @ AgentLinkError::InvalidService
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    /// Anyone can release a slot once the purchased job is done
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseService<'info> {
+    #[account(
+        mut,
+        close = authority,
+        constraint = listing.agent == agent.key() @ AgentLinkError::Unauthorized
+    )]
+    pub listing: Account<'info, ServiceListing>,
+
+    #[account(
+        constraint = authority.key() == agent.authority @ AgentLinkError::Unauthorized
+    )]
+    pub agent: Account<'info, AgentAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(job_id: String)]
+pub struct CreateSubjob<'info> {
+    #[account(
+        mut,
+        constraint = parent_escrow.is_native() @ AgentLinkError::TokenEscrow,
+        constraint = parent_escrow.worker == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
+    pub parent_escrow: Account<'info, EscrowAccount>,
+
+    #[account(mut)]
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    #[account(
+        constraint = parent_escrow.requester == parent_requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub parent_requester_agent: Account<'info, AgentAccount>,
+
+    /// Parent requester's authority, approving the spend from its escrow
+    #[account(
+        constraint = parent_requester.key() == parent_requester_agent.authority @ AgentLinkError::Unauthorized
+    )]
+    pub parent_requester: Signer<'info>,
+
+    #[account(
+        init,
+        payer = worker,
+        space = EscrowAccount::SPACE,
+        seeds = [b"escrow", worker_agent.key().as_ref(), job_id.as_bytes()],
+        bump
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    /// Session key signing for the worker agent (omit when its authority signs)
+    pub session: Option<Account<'info, SessionKey>>,
+
+    #[account(mut)]
+    pub worker: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleSubjob<'info> {
+    #[account(mut)]
+    pub parent_escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        constraint = escrow.parent == parent_escrow.key() @ AgentLinkError::InvalidParent
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    /// Sub-job's worker, required when settling an expired hire
+    #[account(mut)]
+    pub worker_agent: Option<Account<'info, AgentAccount>>,
+
+    /// Anyone can settle a finished sub-job
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrerequisites<'info> {
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    /// Session key signing for the requester agent (omit when its authority signs)
+    pub session: Option<Account<'info, SessionKey>>,
+
+    pub requester: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RecordPrerequisite<'info> {
+    #[account(mut)]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    pub prerequisite: Account<'info, EscrowAccount>,
+
+    /// Anyone can record a completed prerequisite
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(pipeline_id: String)]
+pub struct CreatePipeline<'info> {
+    #[account(
+        constraint = requester.key() == requester_agent.authority @ AgentLinkError::Unauthorized
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    #[account(
+        init,
+        payer = requester,
+        space = Pipeline::SPACE,
+        seeds = [b"pipeline", requester_agent.key().as_ref(), pipeline_id.as_bytes()],
+        bump
+    )]
+    pub pipeline: Account<'info, Pipeline>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u8, job_id: String)]
+pub struct LaunchStage<'info> {
+    #[account(
+        mut,
+        constraint = requester.key() == requester_agent.authority @ AgentLinkError::Unauthorized
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    #[account(
+        mut,
+        constraint = pipeline.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub pipeline: Account<'info, Pipeline>,
+
+    #[account(
+        init,
+        payer = requester,
+        space = EscrowAccount::SPACE,
+        seeds = [b"escrow", requester_agent.key().as_ref(), job_id.as_bytes()],
+        bump
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelPipeline<'info> {
+    #[account(
+        mut,
+        close = requester,
+        constraint = pipeline.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub pipeline: Account<'info, Pipeline>,
+
+    #[account(
+        mut,
+        constraint = requester.key() == requester_agent.authority @ AgentLinkError::Unauthorized
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    #[account(mut)]
+    pub requester: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteJob<'info> {
+    #[account(mut)]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// Session key signing for the worker agent (omit when its authority signs)
+    pub session: Option<Account<'info, SessionKey>>,
+
+    pub worker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveJob<'info> {
+    #[account(
+        mut,
+        constraint = escrow.is_native() @ AgentLinkError::TokenEscrow
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        constraint = escrow.worker == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// CHECK: Worker's creator wallet to receive split
+    #[account(
+        mut,
+        constraint = worker_creator.key() == worker_agent.creator @ AgentLinkError::InvalidCreator
+    )]
+    pub worker_creator: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [b"pair", escrow.requester.as_ref(), escrow.worker.as_ref()],
+        bump = pair.bump
+    )]
+    pub pair: Account<'info, AgentPair>,
+
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    /// Session key signing for the requester agent (omit when its authority signs)
+    #[account(mut)]
+    pub session: Option<Account<'info, SessionKey>>,
+
+    pub requester: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTimeout<'info> {
+    #[account(
+        mut,
+        constraint = escrow.is_native() @ AgentLinkError::TokenEscrow
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        constraint = escrow.worker == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// CHECK: Worker's creator wallet to receive split
+    #[account(
+        mut,
+        constraint = worker_creator.key() == worker_agent.creator @ AgentLinkError::InvalidCreator
+    )]
+    pub worker_creator: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [b"pair", escrow.requester.as_ref(), escrow.worker.as_ref()],
+        bump = pair.bump
+    )]
+    pub pair: Account<'info, AgentPair>,
+
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    /// Anyone can call this after timeout
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevealKey<'info> {
+    #[account(
+        mut,
+        constraint = escrow.is_native() @ AgentLinkError::TokenEscrow
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        constraint = escrow.worker == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// CHECK: Worker's creator wallet to receive split
+    #[account(
+        mut,
+        constraint = worker_creator.key() == worker_agent.creator @ AgentLinkError::InvalidCreator
+    )]
+    pub worker_creator: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [b"pair", escrow.requester.as_ref(), escrow.worker.as_ref()],
+        bump = pair.bump
+    )]
+    pub pair: Account<'info, AgentPair>,
+
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    /// Session key signing for the worker agent (omit when its authority signs)
+    pub session: Option<Account<'info, SessionKey>>,
+
+    pub worker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteMilestone<'info> {
+    #[account(mut)]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// Session key signing for the worker agent (omit when its authority signs)
+    pub session: Option<Account<'info, SessionKey>>,
+
+    pub worker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveMilestone<'info> {
+    #[account(
+        mut,
+        constraint = escrow.is_native() @ AgentLinkError::TokenEscrow
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        constraint = escrow.worker == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// CHECK: Worker's creator wallet to receive split
+    #[account(
+        mut,
+        constraint = worker_creator.key() == worker_agent.creator @ AgentLinkError::InvalidCreator
+    )]
+    pub worker_creator: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [b"pair", escrow.requester.as_ref(), escrow.worker.as_ref()],
+        bump = pair.bump
+    )]
+    pub pair: Account<'info, AgentPair>,
+
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    /// Session key signing for the requester agent (omit when its authority signs)
+    #[account(mut)]
+    pub session: Option<Account<'info, SessionKey>>,
+
+    pub requester: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimMilestoneTimeout<'info> {
+    #[account(
+        mut,
+        constraint = escrow.is_native() @ AgentLinkError::TokenEscrow
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        constraint = escrow.worker == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// CHECK: Worker's creator wallet to receive split
+    #[account(
+        mut,
+        constraint = worker_creator.key() == worker_agent.creator @ AgentLinkError::InvalidCreator
+    )]
+    pub worker_creator: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [b"pair", escrow.requester.as_ref(), escrow.worker.as_ref()],
+        bump = pair.bump
+    )]
+    pub pair: Account<'info, AgentPair>,
+
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    /// Anyone can call this after timeout
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelJob<'info> {
+    #[account(
+        mut,
+        constraint = escrow.is_native() @ AgentLinkError::TokenEscrow
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    /// Session key signing for the requester agent (omit when its authority signs)
+    pub session: Option<Account<'info, SessionKey>>,
+
+    #[account(mut)]
+    pub requester: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimExpired<'info> {
+    #[account(mut)]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        constraint = escrow.worker == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    /// Session key signing for the requester agent (omit when its authority signs)
+    pub session: Option<Account<'info, SessionKey>>,
+
+    #[account(mut)]
+    pub requester: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshReputation<'info> {
+    #[account(mut)]
+    pub agent: Account<'info, AgentAccount>,
+}
+
+#[derive(Accounts)]
+pub struct DisputeJob<'info> {
+    #[account(mut)]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    /// Agent account of the caller (must be requester or worker)
+    #[account(mut)]
+    pub caller_agent: Account<'info, AgentAccount>,
+
+    /// Session key signing for the caller agent (omit when its authority signs)
+    pub session: Option<Account<'info, SessionKey>>,
+
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = ProtocolConfig::SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Treasury::SPACE,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ AgentLinkError::Unauthorized
+    )]
+    pub program: Program<'info, crate::program::Agentlink>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ AgentLinkError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ AgentLinkError::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ AgentLinkError::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasuryToken<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ AgentLinkError::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = treasury,
+        token::token_program = token_program
+    )]
+    pub treasury_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub admin: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterArbiter<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = Arbiter::SPACE,
+        seeds = [b"arbiter", arbiter_authority.key().as_ref()],
+        bump
+    )]
+    pub arbiter: Account<'info, Arbiter>,
+
+    /// CHECK: Wallet that will sign dispute resolutions
+    pub arbiter_authority: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ AgentLinkError::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetArbiterActive<'info> {
+    #[account(mut)]
+    pub arbiter: Account<'info, Arbiter>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ AgentLinkError::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AssignArbiter<'info> {
+    #[account(mut)]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    pub arbiter: Account<'info, Arbiter>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ AgentLinkError::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(
+        mut,
+        constraint = escrow.is_native() @ AgentLinkError::TokenEscrow,
+        constraint = escrow.arbiter == arbiter.key() @ AgentLinkError::InvalidArbiter
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        constraint = escrow.worker == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// CHECK: Worker's creator wallet to receive split
+    #[account(
+        mut,
+        constraint = worker_creator.key() == worker_agent.creator @ AgentLinkError::InvalidCreator
+    )]
+    pub worker_creator: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"pair", escrow.requester.as_ref(), escrow.worker.as_ref()],
+        bump = pair.bump
+    )]
+    pub pair: Account<'info, AgentPair>,
+
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    /// CHECK: Requester's authority wallet to receive the refund
+    #[account(
+        mut,
+        constraint = requester.key() == requester_agent.authority @ AgentLinkError::InvalidRequester
+    )]
+    pub requester: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub arbiter: Account<'info, Arbiter>,
+
+    #[account(
+        constraint = arbiter_authority.key() == arbiter.authority @ AgentLinkError::Unauthorized
+    )]
+    pub arbiter_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureSplit<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == agent.authority @ AgentLinkError::Unauthorized
+    )]
+    pub agent: Account<'info, AgentAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAgentProfile<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == agent.authority @ AgentLinkError::Unauthorized
+    )]
+    pub agent: Account<'info, AgentAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == agent.authority @ AgentLinkError::Unauthorized
+    )]
+    pub agent: Account<'info, AgentAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        constraint = agent.pending_authority != Pubkey::default() @ AgentLinkError::NoPendingAuthority,
+        constraint = new_authority.key() == agent.pending_authority @ AgentLinkError::Unauthorized
+    )]
+    pub agent: Account<'info, AgentAccount>,
 
-        Ok(())
-    }
+    pub new_authority: Signer<'info>,
 }
 
-// Helper function to calculate reputation score
-fn calculate_reputation(successful_jobs: u32, total_earned: u64) -> u16 {
-    let job_points = (successful_jobs as u64).saturating_mul(500);
-    let earned_points = (total_earned / LAMPORTS_PER_SOL).saturating_mul(10);
-    let total = job_points.saturating_add(earned_points);
-    std::cmp::min(total, 10000) as u16
+#[derive(Accounts)]
+pub struct RecoverAuthority<'info> {
+    #[account(
+        mut,
+        constraint = creator.key() == agent.creator @ AgentLinkError::InvalidCreator
+    )]
+    pub agent: Account<'info, AgentAccount>,
+
+    pub creator: Signer<'info>,
 }
 
-// ============== ACCOUNTS ==============
+#[derive(Accounts)]
+#[instruction(key: Pubkey)]
+pub struct CreateSession<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = SessionKey::SPACE,
+        seeds = [b"session", agent.key().as_ref(), key.as_ref()],
+        bump
+    )]
+    pub session: Account<'info, SessionKey>,
 
-#[account]
-pub struct AgentAccount {
-    pub bump: u8,                    // 1
-    pub name: String,                // 4 + MAX_NAME_LENGTH
-    pub creator: Pubkey,             // 32
-    pub authority: Pubkey,           // 32
-    pub created_at: i64,             // 8
-    pub creator_signed: bool,        // 1
-    pub verified: bool,              // 1
-    pub successful_jobs: u32,        // 4
-    pub total_earned: u64,           // 8
-    pub total_spent: u64,            // 8
-    pub reputation_score: u16,       // 2
-    pub creator_split_bps: u16,      // 2
-}
+    #[account(
+        constraint = authority.key() == agent.authority @ AgentLinkError::Unauthorized
+    )]
+    pub agent: Account<'info, AgentAccount>,
 
-impl AgentAccount {
-    pub const SPACE: usize = 8 // discriminator
-        + 1  // bump
-        + 4 + MAX_NAME_LENGTH  // name
-        + 32 // creator
-        + 32 // authority
-        + 8  // created_at
-        + 1  // creator_signed
-        + 1  // verified
-        + 4  // successful_jobs
-        + 8  // total_earned
-        + 8  // total_spent
-        + 2  // reputation_score
-        + 2; // creator_split_bps
-}
+    #[account(mut)]
+    pub authority: Signer<'info>,
 
-#[account]
-pub struct EscrowAccount {
-    pub bump: u8,                    // 1
-    pub job_id: String,              // 4 + MAX_JOB_ID_LENGTH
-    pub job_hash: [u8; 32],          // 32
-    pub requester: Pubkey,           // 32
-    pub worker: Pubkey,              // 32
-    pub amount: u64,                 // 8
-    pub status: u8,                  // 1
-    pub timeout_hours: u8,           // 1
-    pub deadline: i64,               // 8
-    pub created_at: i64,             // 8
+    pub system_program: Program<'info, System>,
 }
 
-impl EscrowAccount {
-    pub const SPACE: usize = 8 // discriminator
-        + 1  // bump
-        + 4 + MAX_JOB_ID_LENGTH  // job_id
-        + 32 // job_hash
-        + 32 // requester
-        + 32 // worker
-        + 8  // amount
-        + 1  // status
-        + 1  // timeout_hours
-        + 8  // deadline
-        + 8; // created_at
-}
+#[derive(Accounts)]
+pub struct RevokeSession<'info> {
+    #[account(
+        mut,
+        close = authority,
+        constraint = session.agent == agent.key() @ AgentLinkError::InvalidSession
+    )]
+    pub session: Account<'info, SessionKey>,
 
-// ============== CONTEXTS ==============
+    #[account(
+        constraint = authority.key() == agent.authority @ AgentLinkError::Unauthorized
+    )]
+    pub agent: Account<'info, AgentAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
 
 #[derive(Accounts)]
-#[instruction(name: String)]
-pub struct RegisterAgent<'info> {
+pub struct Withdraw<'info> {
     #[account(
-        init,
-        payer = creator,
-        space = AgentAccount::SPACE,
-        seeds = [b"agent", creator.key().as_ref(), name.as_bytes()],
-        bump
+        mut,
+        constraint = authority.key() == agent.authority @ AgentLinkError::Unauthorized
     )]
     pub agent: Account<'info, AgentAccount>,
 
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
     #[account(mut)]
-    pub creator: Signer<'info>,
+    pub authority: Signer<'info>,
+}
 
-    pub system_program: Program<'info, System>,
+#[derive(Accounts)]
+pub struct CloseEscrow<'info> {
+    #[account(
+        mut,
+        close = payer,
+        constraint = escrow.is_native() @ AgentLinkError::TokenEscrow
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    /// CHECK: Original rent payer to receive the refund
+    #[account(
+        mut,
+        constraint = payer.key() == escrow.payer @ AgentLinkError::InvalidPayer
+    )]
+    pub payer: AccountInfo<'info>,
+
+    /// Anyone can call this once the escrow is settled
+    pub caller: Signer<'info>,
 }
 
 #[derive(Accounts)]
 #[instruction(job_id: String)]
-pub struct CreateJob<'info> {
+pub struct CreateJobToken<'info> {
+    pub requester_agent: Account<'info, AgentAccount>,
+
     #[account(
         init,
         payer = requester,
         space = EscrowAccount::SPACE,
-        seeds = [b"escrow", job_id.as_bytes()],
+        seeds = [b"escrow", requester_agent.key().as_ref(), job_id.as_bytes()],
+        bump
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        init,
+        payer = requester,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = escrow,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = requester,
+        token::token_program = token_program
+    )]
+    pub requester_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = requester.key() == requester_agent.authority @ AgentLinkError::Unauthorized
+    )]
+    pub requester: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveJobToken<'info> {
+    #[account(
+        mut,
+        constraint = escrow.mint == mint.key() @ AgentLinkError::InvalidMint
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = escrow.worker == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// Worker agent's token account to receive earnings
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = worker_agent,
+        token::token_program = token_program
+    )]
+    pub worker_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// Worker creator's token account to receive split
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = worker_agent.creator,
+        token::token_program = token_program
+    )]
+    pub worker_creator_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    /// Treasury's token account to receive the protocol fee
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = treasury,
+        token::token_program = token_program
+    )]
+    pub treasury_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"pair", escrow.requester.as_ref(), escrow.worker.as_ref()],
+        bump = pair.bump
+    )]
+    pub pair: Account<'info, AgentPair>,
+
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    #[account(
+        constraint = requester.key() == requester_agent.authority @ AgentLinkError::Unauthorized
+    )]
+    pub requester: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTimeoutToken<'info> {
+    #[account(
+        mut,
+        constraint = escrow.mint == mint.key() @ AgentLinkError::InvalidMint
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = escrow.worker == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
+    pub worker_agent: Account<'info, AgentAccount>,
+
+    /// Worker agent's token account to receive earnings
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = worker_agent,
+        token::token_program = token_program
+    )]
+    pub worker_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// Worker creator's token account to receive split
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = worker_agent.creator,
+        token::token_program = token_program
+    )]
+    pub worker_creator_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    /// Treasury's token account to receive the protocol fee
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = treasury,
+        token::token_program = token_program
+    )]
+    pub treasury_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"pair", escrow.requester.as_ref(), escrow.worker.as_ref()],
+        bump = pair.bump
+    )]
+    pub pair: Account<'info, AgentPair>,
+
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    /// Anyone can call this after timeout
+    pub caller: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CancelJobToken<'info> {
+    #[account(
+        mut,
+        constraint = escrow.mint == mint.key() @ AgentLinkError::InvalidMint
+    )]
+    pub escrow: Account<'info, EscrowAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
         bump
     )]
-    pub escrow: Account<'info, EscrowAccount>,
+    pub vault: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+    )]
     pub requester_agent: Account<'info, AgentAccount>,
 
     #[account(
         mut,
+        token::mint = mint,
+        token::authority = requester,
+        token::token_program = token_program
+    )]
+    pub requester_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
         constraint = requester.key() == requester_agent.authority @ AgentLinkError::Unauthorized
     )]
     pub requester: Signer<'info>,
 
-    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct HireAgent<'info> {
-    #[account(mut)]
+pub struct ResolveDisputeToken<'info> {
+    #[account(
+        mut,
+        constraint = escrow.mint == mint.key() @ AgentLinkError::InvalidMint,
+        constraint = escrow.arbiter == arbiter.key() @ AgentLinkError::InvalidArbiter
+    )]
     pub escrow: Account<'info, EscrowAccount>,
 
-    pub worker_agent: Account<'info, AgentAccount>,
-
     #[account(
-        constraint = requester.key() == requester_agent.authority @ AgentLinkError::Unauthorized
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump
     )]
-    pub requester_agent: Account<'info, AgentAccount>,
+    pub vault: InterfaceAccount<'info, TokenAccount>,
 
-    pub requester: Signer<'info>,
-}
-
-#[derive(Accounts)]
-pub struct CompleteJob<'info> {
-    #[account(mut)]
-    pub escrow: Account<'info, EscrowAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
 
+    #[account(
+        mut,
+        constraint = escrow.worker == worker_agent.key() @ AgentLinkError::InvalidWorker
+    )]
     pub worker_agent: Account<'info, AgentAccount>,
 
+    /// Worker agent's token account to receive earnings
     #[account(
-        constraint = worker.key() == worker_agent.authority @ AgentLinkError::Unauthorized
+        mut,
+        token::mint = mint,
+        token::authority = worker_agent,
+        token::token_program = token_program
     )]
-    pub worker: Signer<'info>,
-}
-
-#[derive(Accounts)]
-pub struct ApproveJob<'info> {
-    #[account(mut)]
-    pub escrow: Account<'info, EscrowAccount>,
+    pub worker_token: InterfaceAccount<'info, TokenAccount>,
 
+    /// Worker creator's token account to receive split
     #[account(
         mut,
-        constraint = escrow.worker == worker_agent.key() @ AgentLinkError::InvalidWorker
+        token::mint = mint,
+        token::authority = worker_agent.creator,
+        token::token_program = token_program
     )]
-    pub worker_agent: Account<'info, AgentAccount>,
+    pub worker_creator_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProtocolConfig>,
 
-    /// CHECK: Worker's creator wallet to receive split
     #[account(
         mut,
-        constraint = worker_creator.key() == worker_agent.creator @ AgentLinkError::InvalidCreator
+        seeds = [b"pair", escrow.requester.as_ref(), escrow.worker.as_ref()],
+        bump = pair.bump
     )]
-    pub worker_creator: AccountInfo<'info>,
+    pub pair: Account<'info, AgentPair>,
 
     #[account(
         constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
     )]
     pub requester_agent: Account<'info, AgentAccount>,
 
+    /// Requester authority's token account to receive the refund
     #[account(
-        constraint = requester.key() == requester_agent.authority @ AgentLinkError::Unauthorized
+        mut,
+        token::mint = mint,
+        token::authority = requester_agent.authority,
+        token::token_program = token_program
     )]
-    pub requester: Signer<'info>,
+    pub requester_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub arbiter: Account<'info, Arbiter>,
+
+    #[account(
+        constraint = arbiter_authority.key() == arbiter.authority @ AgentLinkError::Unauthorized
+    )]
+    pub arbiter_authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimTimeout<'info> {
-    #[account(mut)]
-    pub escrow: Account<'info, EscrowAccount>,
+pub struct WithdrawToken<'info> {
+    #[account(
+        constraint = authority.key() == agent.authority @ AgentLinkError::Unauthorized
+    )]
+    pub agent: Account<'info, AgentAccount>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ AgentLinkError::ProgramPaused
+    )]
+    pub config: Account<'info, ProtocolConfig>,
 
     #[account(
         mut,
-        constraint = escrow.worker == worker_agent.key() @ AgentLinkError::InvalidWorker
+        token::mint = mint,
+        token::authority = agent,
+        token::token_program = token_program
     )]
-    pub worker_agent: Account<'info, AgentAccount>,
+    pub agent_token: InterfaceAccount<'info, TokenAccount>,
 
-    /// CHECK: Worker's creator wallet to receive split
     #[account(
         mut,
-        constraint = worker_creator.key() == worker_agent.creator @ AgentLinkError::InvalidCreator
+        token::mint = mint,
+        token::token_program = token_program
     )]
-    pub worker_creator: AccountInfo<'info>,
+    pub destination: InterfaceAccount<'info, TokenAccount>,
 
-    /// Anyone can call this after timeout
-    pub caller: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct CancelJob<'info> {
-    #[account(mut)]
+pub struct CloseEscrowToken<'info> {
+    #[account(
+        mut,
+        close = payer,
+        constraint = escrow.mint == mint.key() @ AgentLinkError::InvalidMint
+    )]
     pub escrow: Account<'info, EscrowAccount>,
 
     #[account(
         mut,
-        constraint = escrow.requester == requester_agent.key() @ AgentLinkError::InvalidRequester
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump
     )]
-    pub requester_agent: Account<'info, AgentAccount>,
+    pub vault: InterfaceAccount<'info, TokenAccount>,
 
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Original rent payer to receive the refund
     #[account(
         mut,
-        constraint = requester.key() == requester_agent.authority @ AgentLinkError::Unauthorized
+        constraint = payer.key() == escrow.payer @ AgentLinkError::InvalidPayer
     )]
-    pub requester: Signer<'info>,
+    pub payer: AccountInfo<'info>,
+
+    /// Anyone can call this once the escrow is settled
+    pub caller: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct DisputeJob<'info> {
+pub struct MigrateAgent<'info> {
+    /// CHECK: Agent account in the original layout, checked by migrate_agent
     #[account(mut)]
-    pub escrow: Account<'info, EscrowAccount>,
+    pub agent: UncheckedAccount<'info>,
 
-    /// Agent account of the caller (must be requester or worker)
-    pub caller_agent: Account<'info, AgentAccount>,
+    /// Anyone can migrate an agent and pays for the extra rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
 
-    #[account(
-        constraint = caller.key() == caller_agent.authority @ AgentLinkError::Unauthorized
-    )]
-    pub caller: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ConfigureSplit<'info> {
+pub struct MigrateEscrow<'info> {
+    /// CHECK: Escrow in the original layout, checked by migrate_escrow
+    #[account(mut)]
+    pub escrow: UncheckedAccount<'info>,
+
+    pub requester_agent: Account<'info, AgentAccount>,
+
+    /// Pays for the extra rent and is refunded when the escrow is closed
     #[account(
         mut,
-        constraint = authority.key() == agent.authority @ AgentLinkError::Unauthorized
+        constraint = requester.key() == requester_agent.authority @ AgentLinkError::Unauthorized
     )]
-    pub agent: Account<'info, AgentAccount>,
+    pub requester: Signer<'info>,
 
-    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-#[derive(Accounts)]
-pub struct Withdraw<'info> {
-    #[account(
-        mut,
-        constraint = authority.key() == agent.authority @ AgentLinkError::Unauthorized
-    )]
-    pub agent: Account<'info, AgentAccount>,
+// ============== EVENTS ==============
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+#[event]
+pub struct AgentRegistered {
+    pub agent: Pubkey,
+    pub creator: Pubkey,
+    pub authority: Pubkey,
+    pub name: String,
+    pub creator_split_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentVerified {
+    pub agent: Pubkey,
+    pub successful_jobs: u32,
+}
+
+#[event]
+pub struct JobCreated {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub job_hash: [u8; 32],
+    pub requester: Pubkey,
+    pub payer: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timeout_hours: u8,
+    pub review_hours: u8,
+    pub milestone_count: u8,
+    pub required_bond: u64,
+    pub hire_mode: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ApplicationSubmitted {
+    pub application: Pubkey,
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub agent: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ApplicationWithdrawn {
+    pub application: Pubkey,
+    pub escrow: Pubkey,
+    pub agent: Pubkey,
+}
+
+#[event]
+pub struct ApplicationRejected {
+    pub application: Pubkey,
+    pub escrow: Pubkey,
+    pub agent: Pubkey,
+}
+
+#[event]
+pub struct BidCommitted {
+    pub bid: Pubkey,
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub agent: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BidRevealed {
+    pub bid: Pubkey,
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub agent: Pubkey,
+    pub amount: u64,
+    pub best_bid: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionSettled {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub worker: Pubkey,
+    pub winning_bid: u64,
+    pub refund: u64,
+    pub forfeited: u64,              // unrevealed bid bonds paid to the requester
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionFailed {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub forfeited: u64,              // unrevealed bid bonds paid to the requester
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BidWithdrawn {
+    pub bid: Pubkey,
+    pub escrow: Pubkey,
+    pub agent: Pubkey,
+}
+
+#[event]
+pub struct AgentHired {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub requester: Pubkey,
+    pub worker: Pubkey,
+    pub deadline: i64,
+    pub bond: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BondSettled {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub slashed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct JobCompleted {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub worker: Pubkey,
+    pub completed_at: i64,
+    pub review_deadline: i64,
+    pub result_hash: [u8; 32],
+    pub result_uri: String,
+    pub key_commitment: [u8; 32],
+}
+
+#[event]
+pub struct KeyRequested {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub requester: Pubkey,
+    pub worker: Pubkey,
+    pub approved_at: i64,
+    pub reveal_deadline: i64,
+}
+
+#[event]
+pub struct KeyRevealed {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub worker: Pubkey,
+    pub decryption_key: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct JobApproved {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub requester: Pubkey,
+    pub worker: Pubkey,
+    pub worker_creator: Pubkey,
+    pub mint: Pubkey,
+    pub result_hash: [u8; 32],
+    pub amount: u64,
+    pub protocol_fee: u64,
+    pub worker_amount: u64,
+    pub creator_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TimeoutClaimed {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub requester: Pubkey,
+    pub worker: Pubkey,
+    pub worker_creator: Pubkey,
+    pub caller: Pubkey,
+    pub mint: Pubkey,
+    pub result_hash: [u8; 32],
+    pub amount: u64,
+    pub protocol_fee: u64,
+    pub worker_amount: u64,
+    pub creator_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MilestoneSubmitted {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub worker: Pubkey,
+    pub index: u8,
+    pub submitted_at: i64,
+}
+
+#[event]
+pub struct MilestoneReleased {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub requester: Pubkey,
+    pub worker: Pubkey,
+    pub index: u8,
+    pub amount: u64,
+    pub protocol_fee: u64,
+    pub worker_amount: u64,
+    pub creator_amount: u64,
+    pub job_completed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct JobCancelled {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub requester: Pubkey,
+    pub mint: Pubkey,
+    pub refund_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct JobExpired {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub requester: Pubkey,
+    pub worker: Pubkey,
+    pub reopened: bool,
+    pub refund_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReputationRefreshed {
+    pub agent: Pubkey,
+    pub old_score: u16,
+    pub new_score: u16,
+}
+
+#[event]
+pub struct JobDisputed {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub disputed_by: Pubkey,
+    pub caller: Pubkey,
+    pub previous_status: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ArbiterRegistered {
+    pub arbiter: Pubkey,
+    pub authority: Pubkey,
+    pub registered_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ArbiterUpdated {
+    pub arbiter: Pubkey,
+    pub authority: Pubkey,
+    pub active: bool,
+}
+
+#[event]
+pub struct ArbiterAssigned {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub arbiter: Pubkey,
+    pub arbiter_authority: Pubkey,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub arbiter: Pubkey,
+    pub requester: Pubkey,
+    pub worker: Pubkey,
+    pub mint: Pubkey,
+    pub worker_share_bps: u16,
+    pub worker_amount: u64,
+    pub creator_amount: u64,
+    pub refund_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub admin: Pubkey,
+    pub max_split_bps: u16,
+    pub default_split_bps: u16,
+    pub verification_threshold: u32,
+    pub allowed_timeouts: [u8; MAX_ALLOWED_TIMEOUTS],
+    pub protocol_fee_bps: u16,
+    pub min_reputation_job_value: u64,
+    pub reputation_mints: Vec<ReputationMint>,
+}
+
+#[event]
+pub struct PauseUpdated {
+    pub admin: Pubkey,
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub admin: Pubkey,
+    pub destination: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct SplitConfigured {
+    pub agent: Pubkey,
+    pub authority: Pubkey,
+    pub old_split_bps: u16,
+    pub new_split_bps: u16,
+}
+
+#[event]
+pub struct SubjobCreated {
+    pub parent: Pubkey,
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub amount: u64,
+    pub open_subjobs: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubjobSettled {
+    pub parent: Pubkey,
+    pub escrow: Pubkey,
+    pub status: u8,
+    pub refund: u64,
+    pub open_subjobs: u16,
+}
+
+#[event]
+pub struct PrerequisitesSet {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub prerequisites: Vec<Pubkey>,
+}
+
+#[event]
+pub struct PrerequisiteMet {
+    pub escrow: Pubkey,
+    pub prerequisite: Pubkey,
+    pub all_met: bool,
+}
+
+#[event]
+pub struct PipelineCreated {
+    pub pipeline: Pubkey,
+    pub requester: Pubkey,
+    pub pipeline_id: String,
+    pub stage_count: u8,
+    pub total: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PipelineStageLaunched {
+    pub pipeline: Pubkey,
+    pub index: u8,
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PipelineCancelled {
+    pub pipeline: Pubkey,
+    pub pipeline_id: String,
+    pub refund: u64,                 // unlaunched budget plus cancelled stages
+    pub stages_launched: u8,
+    pub stages_cancelled: u8,        // open stages cancelled with the pipeline
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ServiceListed {
+    pub listing: Pubkey,
+    pub agent: Pubkey,
+    pub service_id: String,
+    pub price: u64,
+    pub capability: u64,
+    pub max_queue: u16,
+    pub active: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ServicePurchased {
+    pub listing: Pubkey,
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub requester: Pubkey,
+    pub worker: Pubkey,
+    pub price: u64,
+    pub queue_length: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ServiceSlotReleased {
+    pub listing: Pubkey,
+    pub escrow: Pubkey,
+    pub queue_length: u16,
+}
+
+#[event]
+pub struct ServiceClosed {
+    pub listing: Pubkey,
+    pub agent: Pubkey,
+    pub service_id: String,
+    pub total_sales: u32,
+}
+
+#[event]
+pub struct AgentProfileUpdated {
+    pub agent: Pubkey,
+    pub metadata_uri: String,
+    pub metadata_hash: [u8; 32],
+    pub capabilities: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityProposed {
+    pub agent: Pubkey,
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub agent: Pubkey,
+    pub old_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub recovered: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SessionCreated {
+    pub session: Pubkey,
+    pub agent: Pubkey,
+    pub key: Pubkey,
+    pub expires_at: i64,
+    pub allowed_ixs: u16,
+    pub spend_cap: u64,
+}
+
+#[event]
+pub struct SessionRevoked {
+    pub session: Pubkey,
+    pub agent: Pubkey,
+    pub key: Pubkey,
+}
+
+#[event]
+pub struct Withdrawn {
+    pub agent: Pubkey,
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct EscrowClosed {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub payer: Pubkey,
+    pub rent_returned: u64,
+}
+
+#[event]
+pub struct AgentMigrated {
+    pub agent: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowMigrated {
+    pub escrow: Pubkey,
+    pub job_id: String,
+    pub requester: Pubkey,
+    pub timestamp: i64,
 }
 
 // ============== ERRORS ==============
@@ -613,7 +7581,7 @@ pub enum AgentLinkError {
     JobIdTooLong,
     #[msg("Invalid amount")]
     InvalidAmount,
-    #[msg("Invalid timeout (must be 24, 48, or 72 hours)")]
+    #[msg("Invalid timeout (not an allowed delivery window)")]
     InvalidTimeout,
     #[msg("Invalid job status for this operation")]
     InvalidJobStatus,
@@ -627,10 +7595,245 @@ pub enum AgentLinkError {
     InvalidCreator,
     #[msg("Deadline not reached")]
     DeadlineNotReached,
-    #[msg("Creator split too high (max 50%)")]
+    #[msg("Creator split too high")]
     SplitTooHigh,
     #[msg("Insufficient funds")]
     InsufficientFunds,
     #[msg("Nothing to withdraw")]
     NothingToWithdraw,
+    #[msg("Invalid arbiter for this job")]
+    InvalidArbiter,
+    #[msg("Arbiter is not active")]
+    ArbiterInactive,
+    #[msg("Arbiter cannot resolve a job it is party to")]
+    ArbiterConflict,
+    #[msg("Invalid share (max 10000 bps)")]
+    InvalidShare,
+    #[msg("Instruction requires a native SOL escrow")]
+    TokenEscrow,
+    #[msg("Mint does not match escrow")]
+    InvalidMint,
+    #[msg("Invalid milestone count (1 to 8 milestones)")]
+    InvalidMilestoneCount,
+    #[msg("Invalid milestone index")]
+    InvalidMilestone,
+    #[msg("Invalid milestone status for this operation")]
+    InvalidMilestoneStatus,
+    #[msg("Milestone jobs must be completed milestone by milestone")]
+    MilestoneJob,
+    #[msg("A submitted milestone is still pending approval")]
+    MilestonePendingApproval,
+    #[msg("Invalid review window (not an allowed review window)")]
+    InvalidReviewWindow,
+    #[msg("Invalid rent payer")]
+    InvalidPayer,
+    #[msg("Worker must apply or co-sign before being hired")]
+    WorkerConsentRequired,
+    #[msg("Protocol fee too high (max 10%)")]
+    ProtocolFeeTooHigh,
+    #[msg("Invalid protocol config")]
+    InvalidConfig,
+    #[msg("Program is paused")]
+    ProgramPaused,
+    #[msg("Invalid authority")]
+    InvalidAuthority,
+    #[msg("No pending authority transfer")]
+    NoPendingAuthority,
+    #[msg("Invalid session key")]
+    InvalidSession,
+    #[msg("Session key has expired")]
+    SessionExpired,
+    #[msg("Session key is not permitted to sign this instruction")]
+    SessionNotPermitted,
+    #[msg("Session key spend cap exceeded")]
+    SessionSpendCapExceeded,
+    #[msg("Metadata URI too long (max 128 characters)")]
+    MetadataUriTooLong,
+    #[msg("Requester and worker share a creator or authority")]
+    SelfDealing,
+    #[msg("Worker has not posted the required bond")]
+    BondNotPosted,
+    #[msg("Result URI too long (max 128 characters)")]
+    ResultUriTooLong,
+    #[msg("Job has no valid decryption key commitment")]
+    InvalidKeyCommitment,
+    #[msg("Decryption key does not match the commitment")]
+    KeyMismatch,
+    #[msg("Decryption key has already been revealed")]
+    KeyAlreadyRevealed,
+    #[msg("Worker must reveal the decryption key before payout")]
+    KeyNotRevealed,
+    #[msg("Invalid hire mode")]
+    InvalidHireMode,
+    #[msg("Agent does not meet the job's hire requirements")]
+    HireGatesNotMet,
+    #[msg("Job does not use automatic hiring")]
+    AutoHireDisabled,
+    #[msg("Hire window has not closed yet")]
+    HireWindowOpen,
+    #[msg("Every application for the job must be provided once, sorted by key")]
+    IncompleteApplicantSet,
+    #[msg("Another applicant ranks higher")]
+    NotBestApplicant,
+    #[msg("Application does not belong to this job and agent")]
+    InvalidApplication,
+    #[msg("Auction jobs are hired through sealed bids")]
+    AuctionJob,
+    #[msg("Job is not an auction")]
+    NotAuctionJob,
+    #[msg("Auction needs non-zero commit and reveal windows")]
+    InvalidAuctionWindow,
+    #[msg("Bidding window has closed")]
+    BiddingClosed,
+    #[msg("Reveal window is not open")]
+    RevealNotOpen,
+    #[msg("Revealed bid does not match the commitment")]
+    BidMismatch,
+    #[msg("Bid has already been revealed")]
+    BidAlreadyRevealed,
+    #[msg("Invalid bid")]
+    InvalidBid,
+    #[msg("No valid bids were revealed")]
+    NoValidBids,
+    #[msg("Auction is still running")]
+    AuctionInProgress,
+    #[msg("Service ID too long (max 32 characters)")]
+    ServiceIdTooLong,
+    #[msg("Invalid service terms")]
+    InvalidServiceTerms,
+    #[msg("Service is not accepting purchases")]
+    ServiceInactive,
+    #[msg("Service price is above the buyer's limit")]
+    PriceAboveLimit,
+    #[msg("Service queue is full")]
+    ServiceQueueFull,
+    #[msg("Purchased job is still active")]
+    ServiceJobActive,
+    #[msg("Release the service queue slot before closing")]
+    ServiceSlotHeld,
+    #[msg("Escrow was not purchased from this service")]
+    InvalidService,
+    #[msg("Settle all sub-jobs first")]
+    SubjobsOpen,
+    #[msg("Escrow is not a sub-job of this parent")]
+    InvalidParent,
+    #[msg("Sub-job already settled with its parent")]
+    SubjobAlreadySettled,
+    #[msg("Settle the sub-job with its parent before closing")]
+    SubjobNotSettled,
+    #[msg("Too many prerequisites (max 4)")]
+    TooManyPrerequisites,
+    #[msg("Invalid prerequisite")]
+    InvalidPrerequisite,
+    #[msg("Prerequisite jobs are not all completed")]
+    PrerequisitesIncomplete,
+    #[msg("Pipeline needs between 1 and 8 stages")]
+    InvalidPipelineStages,
+    #[msg("Pipeline stage is launched or out of order")]
+    InvalidPipelineStage,
+    #[msg("Delivery deadline has passed")]
+    DeadlinePassed,
+    #[msg("Job has reached its applicant limit")]
+    ApplicantLimitReached,
+    #[msg("Every unrevealed bid must be passed, sorted by key")]
+    UnrevealedBidsMissing,
+    #[msg("Account is not in the original program's layout")]
+    NotLegacyAccount,
+    #[msg("Auction has a valid bid, settle it instead")]
+    AuctionHasWinner,
+    #[msg("Purchased jobs still hold slots in this service's queue")]
+    ServiceQueueNotEmpty,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 86400;
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn reputation_counts_jobs_and_earnings() {
+        assert_eq!(calculate_reputation(0, 0, 0, 0, 0, NOW), 0);
+        assert_eq!(calculate_reputation(20000, 3 * LAMPORTS_PER_SOL, 0, 0, NOW, NOW), 1030);
+    }
+
+    #[test]
+    fn reputation_counts_partial_jobs() {
+        assert_eq!(calculate_reputation(15000, 0, 0, 0, NOW, NOW), 750);
+    }
+
+    #[test]
+    fn reputation_is_capped() {
+        assert_eq!(calculate_reputation(1_000_000, 0, 0, 0, NOW, NOW), 10000);
+    }
+
+    #[test]
+    fn reputation_subtracts_negative_signals() {
+        assert_eq!(calculate_reputation(40000, 0, 1, 0, NOW, NOW), 1000);
+        assert_eq!(calculate_reputation(40000, 0, 0, 1, NOW, NOW), 1250);
+        assert_eq!(calculate_reputation(40000, 0, 2, 1, NOW, NOW), 0);
+    }
+
+    #[test]
+    fn reputation_never_underflows() {
+        assert_eq!(calculate_reputation(0, 0, 5, 5, 0, NOW), 0);
+    }
+
+    #[test]
+    fn reputation_decays_after_grace_period() {
+        let score = calculate_reputation(40000, 0, 0, 0, NOW - 30 * DAY, NOW);
+        assert_eq!(score, 2000);
+
+        let score = calculate_reputation(40000, 0, 0, 0, NOW - 40 * DAY, NOW);
+        assert_eq!(score, 1800);
+    }
+
+    #[test]
+    fn reputation_decay_is_bounded() {
+        let score = calculate_reputation(40000, 0, 0, 0, NOW - 365 * DAY, NOW);
+        assert_eq!(score, 1000);
+    }
+
+    #[test]
+    fn reputation_ignores_decay_without_completed_jobs() {
+        assert_eq!(calculate_reputation(0, 0, 0, 0, 0, NOW + 365 * DAY), 0);
+    }
+
+    #[test]
+    fn weighted_amount_scales_earnings_by_job_weight() {
+        assert_eq!(weighted_amount(3 * LAMPORTS_PER_SOL, 10000), 3 * LAMPORTS_PER_SOL);
+        assert_eq!(weighted_amount(3 * LAMPORTS_PER_SOL, 5000), 3 * LAMPORTS_PER_SOL / 2);
+        assert_eq!(weighted_amount(u64::MAX, 0), 0);
+    }
+
+    #[test]
+    fn job_weight_ignores_jobs_below_minimum_value() {
+        assert_eq!(job_weight_bps(999, 1000, 0), 0);
+        assert_eq!(job_weight_bps(1000, 1000, 0), 10000);
+    }
+
+    #[test]
+    fn job_weight_diminishes_for_repeat_pairs() {
+        assert_eq!(job_weight_bps(1000, 0, 1), 5000);
+        assert_eq!(job_weight_bps(1000, 0, 3), 2500);
+    }
+
+    #[test]
+    fn requester_score_is_zero_without_history() {
+        assert_eq!(calculate_requester_score(0, 0, 0, 0, 0), 0);
+    }
+
+    #[test]
+    fn requester_score_counts_self_approved_jobs() {
+        assert_eq!(calculate_requester_score(4, 0, 0, 0, 0), 10000);
+        assert_eq!(calculate_requester_score(3, 1, 0, 0, 0), 6666);
+        assert_eq!(calculate_requester_score(2, 0, 1, 1, 0), 5000);
+    }
+
+    #[test]
+    fn requester_score_penalizes_slow_approvals() {
+        assert_eq!(calculate_requester_score(4, 0, 0, 0, 36 * 3600), 9000);
+        assert_eq!(calculate_requester_score(4, 0, 0, 0, 1000 * 3600), 8000);
+    }
 }
@@ -1,7 +1,29 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { Agentlink } from "../target/types/agentlink";
-import { PublicKey, Keypair, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import {
+  PublicKey,
+  Keypair,
+  LAMPORTS_PER_SOL,
+  SystemProgram,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  ExtensionType,
+  calculateEpochFee,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createMint,
+  createAccount,
+  getAccount,
+  getMint,
+  getMintLen,
+  getTransferFeeConfig,
+  mintTo,
+} from "@solana/spl-token";
 import { expect } from "chai";
 import * as crypto from "crypto";
 
@@ -15,6 +37,7 @@ describe("agentlink", () => {
   // Test wallets
   const creator1 = Keypair.generate();
   const creator2 = Keypair.generate();
+  const arbiterWallet = Keypair.generate();
 
   // Agent names
   const agent1Name = "matrix";
@@ -23,6 +46,9 @@ describe("agentlink", () => {
   // Job ID
   const jobId = "test-job-001";
 
+  // Hire gates for jobs the requester hires manually
+  const manualHire = { hireMode: 0, minReputation: 0, requireVerified: false, minJobs: 0, hireWindowHours: 0, revealWindowHours: 0 };
+
   // Helper to get agent PDA
   const getAgentPDA = (creator: PublicKey, name: string) => {
     return PublicKey.findProgramAddressSync(
@@ -32,13 +58,61 @@ describe("agentlink", () => {
   };
 
   // Helper to get escrow PDA
-  const getEscrowPDA = (jobId: string) => {
+  const getEscrowPDA = (requesterAgent: PublicKey, jobId: string) => {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), requesterAgent.toBuffer(), Buffer.from(jobId)],
+      program.programId
+    );
+  };
+
+  // Helper to get application PDA
+  const getApplicationPDA = (escrow: PublicKey, agent: PublicKey) => {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("application"), escrow.toBuffer(), agent.toBuffer()],
+      program.programId
+    );
+  };
+
+  // Helper to get requester/worker pair PDA
+  const getPairPDA = (requesterAgent: PublicKey, workerAgent: PublicKey) => {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("pair"), requesterAgent.toBuffer(), workerAgent.toBuffer()],
+      program.programId
+    );
+  };
+
+  // Helper to get token vault PDA
+  const getVaultPDA = (escrow: PublicKey) => {
     return PublicKey.findProgramAddressSync(
-      [Buffer.from("escrow"), Buffer.from(jobId)],
+      [Buffer.from("vault"), escrow.toBuffer()],
       program.programId
     );
   };
 
+  // Helper to get arbiter PDA
+  const getArbiterPDA = (authority: PublicKey) => {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("arbiter"), authority.toBuffer()],
+      program.programId
+    );
+  };
+
+  // Program data account (holds the upgrade authority)
+  const [programDataPDA] = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+
+  // Protocol config and treasury PDAs
+  const [configPDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  const [treasuryPDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("treasury")],
+    program.programId
+  );
+
   // Helper to create job hash
   const createJobHash = (title: string, description: string): number[] => {
     const hash = crypto.createHash("sha256");
@@ -60,6 +134,81 @@ describe("agentlink", () => {
     console.log("Airdropped SOL to test wallets");
   });
 
+  describe("Protocol Config", () => {
+    it("initializes the protocol config and treasury", async () => {
+      await program.methods
+        .initializeConfig(100) // 1% protocol fee
+        .accounts({
+          config: configPDA,
+          treasury: treasuryPDA,
+          program: program.programId,
+          programData: programDataPDA,
+          admin: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      const config = await program.account.protocolConfig.fetch(configPDA);
+      expect(config.admin.toString()).to.equal(provider.wallet.publicKey.toString());
+      expect(config.maxSplitBps).to.equal(5000);
+      expect(config.defaultSplitBps).to.equal(1000);
+      expect(config.verificationThreshold).to.equal(3);
+      expect(config.allowedTimeouts).to.deep.equal([24, 48, 72, 0]);
+      expect(config.protocolFeeBps).to.equal(100);
+
+      console.log("Protocol config initialized with 1% fee");
+    });
+
+    it("rejects config updates from non-admin", async () => {
+      try {
+        await program.methods
+          .updateConfig({
+            maxSplitBps: 5000,
+            defaultSplitBps: 1000,
+            verificationThreshold: 3,
+            allowedTimeouts: [24, 48, 72, 0],
+            protocolFeeBps: 0,
+            minReputationJobValue: new anchor.BN(0.01 * LAMPORTS_PER_SOL),
+            reputationMints: [],
+          })
+          .accounts({
+            config: configPDA,
+            admin: creator1.publicKey,
+          })
+          .signers([creator1])
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+        console.log("Correctly rejected non-admin config update");
+      }
+    });
+
+    it("rejects a protocol fee above the cap", async () => {
+      try {
+        await program.methods
+          .updateConfig({
+            maxSplitBps: 5000,
+            defaultSplitBps: 1000,
+            verificationThreshold: 3,
+            allowedTimeouts: [24, 48, 72, 0],
+            protocolFeeBps: 2000, // 20% - above max of 10%
+            minReputationJobValue: new anchor.BN(0.01 * LAMPORTS_PER_SOL),
+            reputationMints: [],
+          })
+          .accounts({
+            config: configPDA,
+            admin: provider.wallet.publicKey,
+          })
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ProtocolFeeTooHigh");
+        console.log("Correctly rejected protocol fee above cap");
+      }
+    });
+  });
+
   describe("Agent Registration", () => {
     it("registers an agent successfully", async () => {
       const [agentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
@@ -67,6 +216,7 @@ describe("agentlink", () => {
       await program.methods
         .registerAgent(agent1Name)
         .accounts({
+          config: configPDA,
           agent: agentPDA,
           creator: creator1.publicKey,
           systemProgram: anchor.web3.SystemProgram.programId,
@@ -87,6 +237,7 @@ describe("agentlink", () => {
       expect(agent.totalSpent.toNumber()).to.equal(0);
       expect(agent.reputationScore).to.equal(0);
       expect(agent.creatorSplitBps).to.equal(1000); // 10% default
+      expect(agent.noShows).to.equal(0);
 
       console.log(`Agent '${agent1Name}' registered successfully`);
     });
@@ -97,6 +248,7 @@ describe("agentlink", () => {
       await program.methods
         .registerAgent(agent2Name)
         .accounts({
+          config: configPDA,
           agent: agentPDA,
           creator: creator2.publicKey,
           systemProgram: anchor.web3.SystemProgram.programId,
@@ -117,6 +269,7 @@ describe("agentlink", () => {
         await program.methods
           .registerAgent("")
           .accounts({
+            config: configPDA,
             agent: agentPDA,
             creator: creator1.publicKey,
             systemProgram: anchor.web3.SystemProgram.programId,
@@ -131,10 +284,105 @@ describe("agentlink", () => {
     });
   });
 
+  describe("Agent Profile", () => {
+    it("publishes metadata and capabilities on-chain", async () => {
+      const [agentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const metadataUri = "https://agentlink.example/agents/oracle.json";
+      const metadataHash = createJobHash("oracle", "profile");
+
+      await program.methods
+        .updateAgentProfile(metadataUri, metadataHash, new anchor.BN(0b101))
+        .accounts({
+          agent: agentPDA,
+          authority: creator2.publicKey,
+        })
+        .signers([creator2])
+        .rpc();
+
+      const agent = await program.account.agentAccount.fetch(agentPDA);
+      expect(agent.metadataUri).to.equal(metadataUri);
+      expect(agent.metadataHash).to.deep.equal(metadataHash);
+      expect(agent.capabilities.toNumber()).to.equal(0b101);
+
+      console.log(`Agent '${agent2Name}' profile published`);
+    });
+
+    it("fails with a metadata URI that is too long", async () => {
+      const [agentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+
+      try {
+        await program.methods
+          .updateAgentProfile("x".repeat(129), createJobHash("too", "long"), new anchor.BN(0))
+          .accounts({
+            agent: agentPDA,
+            authority: creator2.publicKey,
+          })
+          .signers([creator2])
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("MetadataUriTooLong");
+      }
+    });
+  });
+
+  describe("Authority Transfer", () => {
+    const hotKey = Keypair.generate();
+
+    it("proposes and accepts a new authority", async () => {
+      const [agentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+
+      await program.methods
+        .proposeAuthority(hotKey.publicKey)
+        .accounts({
+          agent: agentPDA,
+          authority: creator1.publicKey,
+        })
+        .signers([creator1])
+        .rpc();
+
+      let agent = await program.account.agentAccount.fetch(agentPDA);
+      expect(agent.pendingAuthority.toString()).to.equal(hotKey.publicKey.toString());
+
+      await program.methods
+        .acceptAuthority()
+        .accounts({
+          agent: agentPDA,
+          newAuthority: hotKey.publicKey,
+        })
+        .signers([hotKey])
+        .rpc();
+
+      agent = await program.account.agentAccount.fetch(agentPDA);
+      expect(agent.authority.toString()).to.equal(hotKey.publicKey.toString());
+      expect(agent.pendingAuthority.toString()).to.equal(PublicKey.default.toString());
+
+      console.log(`Agent '${agent1Name}' authority rotated to ${hotKey.publicKey.toString()}`);
+    });
+
+    it("creator recovers the agent authority", async () => {
+      const [agentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+
+      await program.methods
+        .recoverAuthority(creator1.publicKey)
+        .accounts({
+          agent: agentPDA,
+          creator: creator1.publicKey,
+        })
+        .signers([creator1])
+        .rpc();
+
+      const agent = await program.account.agentAccount.fetch(agentPDA);
+      expect(agent.authority.toString()).to.equal(creator1.publicKey.toString());
+
+      console.log(`Agent '${agent1Name}' authority recovered by creator`);
+    });
+  });
+
   describe("Job Creation", () => {
     it("creates a job with escrow", async () => {
       const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
-      const [escrowPDA] = getEscrowPDA(jobId);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, jobId);
 
       const amount = 0.1 * LAMPORTS_PER_SOL;
       const jobHash = createJobHash("Test Job", "This is a test job description");
@@ -142,11 +390,13 @@ describe("agentlink", () => {
       const creator1BalanceBefore = await provider.connection.getBalance(creator1.publicKey);
 
       await program.methods
-        .createJob(jobId, jobHash, new anchor.BN(amount), 24)
+        .createJob(jobId, jobHash, new anchor.BN(amount), 24, 24, new anchor.BN(0), manualHire)
         .accounts({
+          config: configPDA,
           escrow: escrowPDA,
           requesterAgent: requesterAgentPDA,
           requester: creator1.publicKey,
+          session: null,
           systemProgram: anchor.web3.SystemProgram.programId,
         })
         .signers([creator1])
@@ -161,6 +411,7 @@ describe("agentlink", () => {
       expect(escrow.amount.toNumber()).to.equal(amount);
       expect(escrow.status).to.equal(0); // STATUS_OPEN
       expect(escrow.timeoutHours).to.equal(24);
+      expect(escrow.reviewHours).to.equal(24);
 
       // Verify requester's total_spent updated
       const requesterAgent = await program.account.agentAccount.fetch(requesterAgentPDA);
@@ -171,17 +422,19 @@ describe("agentlink", () => {
 
     it("fails to create job with invalid timeout", async () => {
       const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
-      const [escrowPDA] = getEscrowPDA("invalid-timeout-job");
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, "invalid-timeout-job");
 
       const jobHash = createJobHash("Test", "Test");
 
       try {
         await program.methods
-          .createJob("invalid-timeout-job", jobHash, new anchor.BN(LAMPORTS_PER_SOL), 12) // 12 hours not allowed
+          .createJob("invalid-timeout-job", jobHash, new anchor.BN(LAMPORTS_PER_SOL), 12, 24, new anchor.BN(0), manualHire) // 12 hours not allowed
           .accounts({
+            config: configPDA,
             escrow: escrowPDA,
             requesterAgent: requesterAgentPDA,
             requester: creator1.publicKey,
+            session: null,
             systemProgram: anchor.web3.SystemProgram.programId,
           })
           .signers([creator1])
@@ -192,21 +445,182 @@ describe("agentlink", () => {
         expect(err.message).to.include("InvalidTimeout");
       }
     });
+
+    it("fails to create job with invalid review window", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, "invalid-review-job");
+
+      const jobHash = createJobHash("Test", "Test");
+
+      try {
+        await program.methods
+          .createJob("invalid-review-job", jobHash, new anchor.BN(LAMPORTS_PER_SOL), 24, 6, new anchor.BN(0), manualHire) // 6 hours not allowed
+          .accounts({
+            config: configPDA,
+            escrow: escrowPDA,
+            requesterAgent: requesterAgentPDA,
+            requester: creator1.publicKey,
+            session: null,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([creator1])
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.message).to.include("InvalidReviewWindow");
+      }
+    });
+  });
+
+  describe("Events", () => {
+    it("emits JobCreated with the escrowed amount", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const eventJobId = "event-test-job";
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, eventJobId);
+      const amount = 0.01 * LAMPORTS_PER_SOL;
+
+      let listener: number;
+      const event = new Promise<any>((resolve) => {
+        listener = program.addEventListener("jobCreated", (e) => resolve(e));
+      });
+
+      await program.methods
+        .createJob(eventJobId, createJobHash("Event", "Test"), new anchor.BN(amount), 24, 24, new anchor.BN(0), manualHire)
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          requesterAgent: requesterAgentPDA,
+          requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1])
+        .rpc();
+
+      const jobCreated = await event;
+      await program.removeEventListener(listener);
+
+      expect(jobCreated.jobId).to.equal(eventJobId);
+      expect(jobCreated.escrow.toString()).to.equal(escrowPDA.toString());
+      expect(jobCreated.amount.toNumber()).to.equal(amount);
+    });
   });
 
   describe("Hiring Flow", () => {
+    it("fails to hire an agent that has not applied", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, jobId);
+
+      try {
+        await program.methods
+          .hireAgent()
+          .accounts({
+            config: configPDA,
+            escrow: escrowPDA,
+            workerAgent: workerAgentPDA,
+            application: null,
+            worker: null,
+            requesterAgent: requesterAgentPDA,
+            pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
+            requester: creator1.publicKey,
+            session: null,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([creator1])
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.message).to.include("WorkerConsentRequired");
+      }
+    });
+
+    it("refuses to hire an agent with the same creator", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator1.publicKey, "matrix-alt");
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, jobId);
+
+      await program.methods
+        .registerAgent("matrix-alt")
+        .accounts({
+          agent: workerAgentPDA,
+          config: configPDA,
+          creator: creator1.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1])
+        .rpc();
+
+      try {
+        await program.methods
+          .hireAgent()
+          .accounts({
+            config: configPDA,
+            escrow: escrowPDA,
+            workerAgent: workerAgentPDA,
+            application: null,
+            worker: creator1.publicKey,
+            requesterAgent: requesterAgentPDA,
+            pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
+            requester: creator1.publicKey,
+            session: null,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([creator1])
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.message).to.include("SelfDealing");
+      }
+    });
+
+    it("worker applies to the job", async () => {
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, jobId);
+      const [applicationPDA] = getApplicationPDA(escrowPDA, workerAgentPDA);
+
+      await program.methods
+        .applyToJob()
+        .accounts({
+          application: applicationPDA,
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          worker: creator2.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator2])
+        .rpc();
+
+      const application = await program.account.application.fetch(applicationPDA);
+      expect(application.agent.toString()).to.equal(workerAgentPDA.toString());
+
+      console.log(`Agent '${agent2Name}' applied to job '${jobId}'`);
+    });
+
     it("hires an agent for the job", async () => {
       const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
       const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
-      const [escrowPDA] = getEscrowPDA(jobId);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, jobId);
+      const [applicationPDA] = getApplicationPDA(escrowPDA, workerAgentPDA);
 
       await program.methods
         .hireAgent()
         .accounts({
+          config: configPDA,
           escrow: escrowPDA,
           workerAgent: workerAgentPDA,
+          application: applicationPDA,
+          worker: null,
           requesterAgent: requesterAgentPDA,
+          pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
           requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
         })
         .signers([creator1])
         .rpc();
@@ -224,20 +638,27 @@ describe("agentlink", () => {
   describe("Job Completion", () => {
     it("worker marks job as complete", async () => {
       const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
-      const [escrowPDA] = getEscrowPDA(jobId);
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, jobId);
+      const resultHash = createJobHash("Result", "Delivered analysis");
+      const resultUri = "ipfs://bafy-result-analysis";
 
       await program.methods
-        .completeJob()
+        .completeJob(resultHash, resultUri, null)
         .accounts({
           escrow: escrowPDA,
           workerAgent: workerAgentPDA,
           worker: creator2.publicKey,
+          session: null,
         })
         .signers([creator2])
         .rpc();
 
       const escrow = await program.account.escrowAccount.fetch(escrowPDA);
       expect(escrow.status).to.equal(2); // STATUS_PENDING_APPROVAL
+      expect(escrow.completedAt.toNumber()).to.be.greaterThan(0);
+      expect(escrow.resultHash).to.deep.equal(resultHash);
+      expect(escrow.resultUri).to.equal(resultUri);
 
       console.log(`Job '${jobId}' marked as complete`);
     });
@@ -247,19 +668,25 @@ describe("agentlink", () => {
     it("requester approves job and releases payment", async () => {
       const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
       const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
-      const [escrowPDA] = getEscrowPDA(jobId);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, jobId);
 
       const workerAgentBefore = await program.account.agentAccount.fetch(workerAgentPDA);
       const creator2BalanceBefore = await provider.connection.getBalance(creator2.publicKey);
+      const treasuryBalanceBefore = await provider.connection.getBalance(treasuryPDA);
+      const escrowBefore = await program.account.escrowAccount.fetch(escrowPDA);
 
       await program.methods
         .approveJob()
         .accounts({
+          config: configPDA,
+          treasury: treasuryPDA,
           escrow: escrowPDA,
           workerAgent: workerAgentPDA,
           workerCreator: creator2.publicKey, // Worker's creator receives split
           requesterAgent: requesterAgentPDA,
+          pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
           requester: creator1.publicKey,
+          session: null,
         })
         .signers([creator1])
         .rpc();
@@ -274,6 +701,23 @@ describe("agentlink", () => {
       expect(workerAgent.totalEarned.toNumber()).to.be.greaterThan(0);
       expect(workerAgent.reputationScore).to.be.greaterThan(0);
 
+      // Verify the first deal between this pair carries full weight
+      const pair = await program.account.agentPair.fetch(getPairPDA(requesterAgentPDA, workerAgentPDA)[0]);
+      expect(pair.jobsCompleted).to.equal(1);
+      expect(workerAgent.weightedJobsBps.toNumber()).to.equal(10000);
+      expect(workerAgent.weightedEarned.toNumber()).to.equal(workerAgent.totalEarned.toNumber());
+
+      // Verify requester-side reputation updated
+      const requesterAgent = await program.account.agentAccount.fetch(requesterAgentPDA);
+      expect(requesterAgent.jobsPaid).to.equal(1);
+      expect(requesterAgent.timeoutsSuffered).to.equal(0);
+      expect(requesterAgent.requesterScore).to.be.greaterThan(0);
+
+      // Verify protocol fee reached the treasury
+      const expectedFee = escrowBefore.amount.toNumber() / 100;
+      const treasuryBalance = await provider.connection.getBalance(treasuryPDA);
+      expect(treasuryBalance - treasuryBalanceBefore).to.equal(expectedFee);
+
       console.log(`Job '${jobId}' approved! Worker stats updated.`);
       console.log(`  - Successful jobs: ${workerAgent.successfulJobs}`);
       console.log(`  - Total earned: ${workerAgent.totalEarned.toNumber() / LAMPORTS_PER_SOL} SOL`);
@@ -281,58 +725,1094 @@ describe("agentlink", () => {
     });
   });
 
-  describe("Job Cancellation", () => {
-    const cancelJobId = "cancel-test-job";
-
-    it("creates a job to cancel", async () => {
-      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
-      const [escrowPDA] = getEscrowPDA(cancelJobId);
+  describe("Session Keys", () => {
+    const sessionJobId = "session-test-job";
+    const hotKey = Keypair.generate();
+
+    const getSessionPDA = (agent: PublicKey, key: PublicKey) => {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("session"), agent.toBuffer(), key.toBuffer()],
+        program.programId
+      );
+    };
+
+    before(async () => {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(hotKey.publicKey, LAMPORTS_PER_SOL)
+      );
+    });
 
-      const amount = 0.05 * LAMPORTS_PER_SOL;
-      const jobHash = createJobHash("Cancel Test", "This job will be cancelled");
+    it("creates a session key scoped to job creation and cancellation", async () => {
+      const [agentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [sessionPDA] = getSessionPDA(agentPDA, hotKey.publicKey);
+      const expiresAt = Math.floor(Date.now() / 1000) + 3600;
 
       await program.methods
-        .createJob(cancelJobId, jobHash, new anchor.BN(amount), 48)
+        .createSession(
+          hotKey.publicKey,
+          new anchor.BN(expiresAt),
+          (1 << 0) | (1 << 5), // SESSION_CREATE_JOB | SESSION_CANCEL
+          new anchor.BN(0.05 * LAMPORTS_PER_SOL)
+        )
         .accounts({
-          escrow: escrowPDA,
-          requesterAgent: requesterAgentPDA,
-          requester: creator1.publicKey,
+          session: sessionPDA,
+          agent: agentPDA,
+          authority: creator2.publicKey,
           systemProgram: anchor.web3.SystemProgram.programId,
         })
-        .signers([creator1])
+        .signers([creator2])
         .rpc();
 
-      console.log(`Created job '${cancelJobId}' for cancellation test`);
+      const session = await program.account.sessionKey.fetch(sessionPDA);
+      expect(session.key.toString()).to.equal(hotKey.publicKey.toString());
+      expect(session.spent.toNumber()).to.equal(0);
+
+      console.log(`Session key ${hotKey.publicKey.toString()} created for '${agent2Name}'`);
     });
 
-    it("cancels job and refunds escrow", async () => {
-      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
-      const [escrowPDA] = getEscrowPDA(cancelJobId);
+    it("session key funds a job from the agent PDA and cancels it", async () => {
+      const [agentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [sessionPDA] = getSessionPDA(agentPDA, hotKey.publicKey);
+      const [escrowPDA] = getEscrowPDA(agentPDA, sessionJobId);
+      const amount = 0.01 * LAMPORTS_PER_SOL;
 
-      const creator1BalanceBefore = await provider.connection.getBalance(creator1.publicKey);
-      const requesterAgentBefore = await program.account.agentAccount.fetch(requesterAgentPDA);
+      const agentBalanceBefore = await provider.connection.getBalance(agentPDA);
 
       await program.methods
-        .cancelJob()
+        .createJob(sessionJobId, createJobHash("Session", "Test"), new anchor.BN(amount), 24, 24, new anchor.BN(0), manualHire)
         .accounts({
+          config: configPDA,
           escrow: escrowPDA,
-          requesterAgent: requesterAgentPDA,
-          requester: creator1.publicKey,
+          requesterAgent: agentPDA,
+          requester: hotKey.publicKey,
+          session: sessionPDA,
+          systemProgram: anchor.web3.SystemProgram.programId,
         })
-        .signers([creator1])
+        .signers([hotKey])
         .rpc();
 
-      // Verify escrow cancelled
       const escrow = await program.account.escrowAccount.fetch(escrowPDA);
-      expect(escrow.status).to.equal(5); // STATUS_CANCELLED
-      expect(escrow.amount.toNumber()).to.equal(0);
+      expect(escrow.fundedByAgent).to.be.true;
+      expect(await provider.connection.getBalance(agentPDA)).to.equal(agentBalanceBefore - amount);
 
-      // Verify total_spent reduced
-      const requesterAgent = await program.account.agentAccount.fetch(requesterAgentPDA);
-      expect(requesterAgent.totalSpent.toNumber()).to.be.lessThan(requesterAgentBefore.totalSpent.toNumber());
+      const session = await program.account.sessionKey.fetch(sessionPDA);
+      expect(session.spent.toNumber()).to.equal(amount);
+
+      await program.methods
+        .cancelJob()
+        .accounts({
+          escrow: escrowPDA,
+          requesterAgent: agentPDA,
+          requester: hotKey.publicKey,
+          session: sessionPDA,
+        })
+        .signers([hotKey])
+        .rpc();
+
+      expect(await provider.connection.getBalance(agentPDA)).to.equal(agentBalanceBefore);
+
+      console.log(`Job '${sessionJobId}' funded and refunded through session key`);
+    });
+
+    it("session key cannot withdraw earnings", async () => {
+      const [agentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+
+      try {
+        await program.methods
+          .withdraw(new anchor.BN(0))
+          .accounts({
+            agent: agentPDA,
+            config: configPDA,
+            authority: hotKey.publicKey,
+          })
+          .signers([hotKey])
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("Unauthorized");
+        console.log("Correctly rejected withdrawal by session key");
+      }
+    });
+  });
+
+  describe("Job Cancellation", () => {
+    const cancelJobId = "cancel-test-job";
+
+    it("creates a job to cancel", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, cancelJobId);
+
+      const amount = 0.05 * LAMPORTS_PER_SOL;
+      const jobHash = createJobHash("Cancel Test", "This job will be cancelled");
+
+      await program.methods
+        .createJob(cancelJobId, jobHash, new anchor.BN(amount), 48, 24, new anchor.BN(0), manualHire)
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          requesterAgent: requesterAgentPDA,
+          requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1])
+        .rpc();
+
+      console.log(`Created job '${cancelJobId}' for cancellation test`);
+    });
+
+    it("cancels job and refunds escrow", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, cancelJobId);
+
+      const creator1BalanceBefore = await provider.connection.getBalance(creator1.publicKey);
+      const requesterAgentBefore = await program.account.agentAccount.fetch(requesterAgentPDA);
+
+      await program.methods
+        .cancelJob()
+        .accounts({
+          escrow: escrowPDA,
+          requesterAgent: requesterAgentPDA,
+          requester: creator1.publicKey,
+          session: null,
+        })
+        .signers([creator1])
+        .rpc();
+
+      // Verify escrow cancelled
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.status).to.equal(5); // STATUS_CANCELLED
+      expect(escrow.amount.toNumber()).to.equal(0);
+
+      // Verify total_spent reduced
+      const requesterAgent = await program.account.agentAccount.fetch(requesterAgentPDA);
+      expect(requesterAgent.totalSpent.toNumber()).to.be.lessThan(requesterAgentBefore.totalSpent.toNumber());
+
+      expect(requesterAgent.cancellations).to.equal(requesterAgentBefore.cancellations + 1);
 
       console.log(`Job '${cancelJobId}' cancelled and refunded`);
     });
+
+    it("closes the cancelled escrow and returns rent", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, cancelJobId);
+
+      await program.methods
+        .closeEscrow()
+        .accounts({
+          escrow: escrowPDA,
+          payer: creator1.publicKey,
+          caller: creator2.publicKey,
+        })
+        .signers([creator2])
+        .rpc();
+
+      const escrowInfo = await provider.connection.getAccountInfo(escrowPDA);
+      expect(escrowInfo).to.be.null;
+
+      console.log(`Escrow for '${cancelJobId}' closed`);
+    });
+  });
+
+  describe("Pause Switch", () => {
+    const pauseJobId = "pause-test-job";
+
+    it("blocks job creation while paused but still allows cancellation", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, pauseJobId);
+      const jobHash = createJobHash("Pause Test", "Created before the pause");
+
+      await program.methods
+        .createJob(pauseJobId, jobHash, new anchor.BN(0.05 * LAMPORTS_PER_SOL), 24, 24, new anchor.BN(0), manualHire)
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          requesterAgent: requesterAgentPDA,
+          requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1])
+        .rpc();
+
+      await program.methods
+        .setPaused(true)
+        .accounts({
+          config: configPDA,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      try {
+        const [blockedEscrowPDA] = getEscrowPDA(requesterAgentPDA, "paused-job");
+        await program.methods
+          .createJob("paused-job", jobHash, new anchor.BN(0.05 * LAMPORTS_PER_SOL), 24, 24, new anchor.BN(0), manualHire)
+          .accounts({
+            config: configPDA,
+            escrow: blockedEscrowPDA,
+            requesterAgent: requesterAgentPDA,
+            requester: creator1.publicKey,
+            session: null,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([creator1])
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ProgramPaused");
+        console.log("Correctly blocked job creation while paused");
+      }
+
+      await program.methods
+        .cancelJob()
+        .accounts({
+          escrow: escrowPDA,
+          requesterAgent: requesterAgentPDA,
+          requester: creator1.publicKey,
+          session: null,
+        })
+        .signers([creator1])
+        .rpc();
+
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.status).to.equal(5); // STATUS_CANCELLED
+
+      await program.methods
+        .setPaused(false)
+        .accounts({
+          config: configPDA,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      const config = await program.account.protocolConfig.fetch(configPDA);
+      expect(config.paused).to.be.false;
+
+      console.log(`Job '${pauseJobId}' cancelled while paused`);
+    });
+  });
+
+  describe("Milestone Jobs", () => {
+    const milestoneJobId = "milestone-test-job";
+    const milestoneAmount = 0.02 * LAMPORTS_PER_SOL;
+
+    it("creates a job with two milestones and hires a worker", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, milestoneJobId);
+
+      const jobHash = createJobHash("Milestone Test", "Two stage job");
+      const milestones = [
+        { amount: new anchor.BN(milestoneAmount), milestoneHash: createJobHash("Stage", "1") },
+        { amount: new anchor.BN(milestoneAmount), milestoneHash: createJobHash("Stage", "2") },
+      ];
+
+      await program.methods
+        .createMilestoneJob(milestoneJobId, jobHash, milestones, 24, 24, new anchor.BN(0), manualHire)
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          requesterAgent: requesterAgentPDA,
+          requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1])
+        .rpc();
+
+      await program.methods
+        .hireAgent()
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          application: null,
+          worker: creator2.publicKey, // worker co-signs instead of applying
+          requesterAgent: requesterAgentPDA,
+          pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
+          requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1, creator2])
+        .rpc();
+
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.milestones.length).to.equal(2);
+      expect(escrow.amount.toNumber()).to.equal(2 * milestoneAmount);
+
+      console.log(`Milestone job '${milestoneJobId}' created and hired`);
+    });
+
+    it("submits and approves the first milestone", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, milestoneJobId);
+
+      await program.methods
+        .completeMilestone(0)
+        .accounts({
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          worker: creator2.publicKey,
+          session: null,
+        })
+        .signers([creator2])
+        .rpc();
+
+      await program.methods
+        .approveMilestone(0)
+        .accounts({
+          config: configPDA,
+          treasury: treasuryPDA,
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          workerCreator: creator2.publicKey,
+          requesterAgent: requesterAgentPDA,
+          pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
+          requester: creator1.publicKey,
+          session: null,
+        })
+        .signers([creator1])
+        .rpc();
+
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.status).to.equal(1); // still STATUS_IN_PROGRESS
+      expect(escrow.milestones[0].status).to.equal(2); // MILESTONE_RELEASED
+      expect(escrow.amount.toNumber()).to.equal(milestoneAmount);
+
+      console.log(`Milestone 0 of '${milestoneJobId}' released`);
+    });
+  });
+
+  describe("Expired Jobs", () => {
+    it("refuses to reclaim before the delivery deadline", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, "milestone-test-job");
+
+      try {
+        await program.methods
+          .reclaimExpired(false)
+          .accounts({
+            escrow: escrowPDA,
+            workerAgent: workerAgentPDA,
+            requesterAgent: requesterAgentPDA,
+            requester: creator1.publicKey,
+            session: null,
+          })
+          .signers([creator1])
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.message).to.include("DeadlineNotReached");
+      }
+    });
+  });
+
+  describe("Worker Bonds", () => {
+    const bondJobId = "bond-test-job";
+    const bond = 0.02 * LAMPORTS_PER_SOL;
+
+    it("moves the bond posted with an application into the escrow at hire", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, bondJobId);
+      const [applicationPDA] = getApplicationPDA(escrowPDA, workerAgentPDA);
+      const jobHash = createJobHash("Bond Test", "Worker posts collateral");
+
+      await program.methods
+        .createJob(bondJobId, jobHash, new anchor.BN(0.05 * LAMPORTS_PER_SOL), 24, 24, new anchor.BN(bond), manualHire)
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          requesterAgent: requesterAgentPDA,
+          requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1])
+        .rpc();
+
+      await program.methods
+        .applyToJob()
+        .accounts({
+          application: applicationPDA,
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          worker: creator2.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator2])
+        .rpc();
+
+      const application = await program.account.application.fetch(applicationPDA);
+      expect(application.bond.toNumber()).to.equal(bond);
+
+      const escrowBalanceBefore = await provider.connection.getBalance(escrowPDA);
+
+      await program.methods
+        .hireAgent()
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          application: applicationPDA,
+          worker: null,
+          requesterAgent: requesterAgentPDA,
+          pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
+          requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1])
+        .rpc();
+
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.bond.toNumber()).to.equal(bond);
+      expect(await provider.connection.getBalance(escrowPDA)).to.equal(escrowBalanceBefore + bond);
+
+      console.log(`Job '${bondJobId}' hired with a ${bond / LAMPORTS_PER_SOL} SOL bond`);
+    });
+  });
+
+  describe("Fair Exchange", () => {
+    const fxJobId = "fair-exchange-job";
+    const decryptionKey = Array.from(crypto.randomBytes(32));
+    const keyCommitment = Array.from(crypto.createHash("sha256").update(Buffer.from(decryptionKey)).digest());
+
+    it("holds an approved payout until the worker reveals the committed key", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, fxJobId);
+      const [pairPDA] = getPairPDA(requesterAgentPDA, workerAgentPDA);
+
+      await program.methods
+        .createJob(fxJobId, createJobHash("Fair Exchange", "Encrypted delivery"), new anchor.BN(0.05 * LAMPORTS_PER_SOL), 24, 24, new anchor.BN(0), manualHire)
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          requesterAgent: requesterAgentPDA,
+          requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1])
+        .rpc();
+
+      await program.methods
+        .hireAgent()
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          application: null,
+          worker: creator2.publicKey,
+          requesterAgent: requesterAgentPDA,
+          pair: pairPDA,
+          requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1, creator2])
+        .rpc();
+
+      await program.methods
+        .completeJob(createJobHash("Encrypted", "Output"), null, keyCommitment)
+        .accounts({
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          worker: creator2.publicKey,
+          session: null,
+        })
+        .signers([creator2])
+        .rpc();
+
+      await program.methods
+        .approveJob()
+        .accounts({
+          config: configPDA,
+          treasury: treasuryPDA,
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          workerCreator: creator2.publicKey,
+          requesterAgent: requesterAgentPDA,
+          pair: pairPDA,
+          requester: creator1.publicKey,
+          session: null,
+        })
+        .signers([creator1])
+        .rpc();
+
+      let escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.status).to.equal(7); // STATUS_AWAITING_KEY
+
+      const revealAccounts = {
+        escrow: escrowPDA,
+        workerAgent: workerAgentPDA,
+        workerCreator: creator2.publicKey,
+        config: configPDA,
+        treasury: treasuryPDA,
+        pair: pairPDA,
+        requesterAgent: requesterAgentPDA,
+        session: null,
+        worker: creator2.publicKey,
+      };
+
+      try {
+        await program.methods
+          .revealKey(Array.from(crypto.randomBytes(32)))
+          .accounts(revealAccounts)
+          .signers([creator2])
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("KeyMismatch");
+      }
+
+      const workerBalanceBefore = await provider.connection.getBalance(workerAgentPDA);
+
+      await program.methods
+        .revealKey(decryptionKey)
+        .accounts(revealAccounts)
+        .signers([creator2])
+        .rpc();
+
+      escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.status).to.equal(3); // STATUS_COMPLETED
+      expect(escrow.keyRevealed).to.be.true;
+      expect(escrow.decryptionKey).to.deep.equal(decryptionKey);
+      expect(await provider.connection.getBalance(workerAgentPDA)).to.be.greaterThan(workerBalanceBefore);
+
+      console.log(`Job '${fxJobId}' settled on key reveal`);
+    });
+  });
+
+  describe("Auto-Hire", () => {
+    it("rejects applicants below the job's reputation gates", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, "gated-job");
+      const [applicationPDA] = getApplicationPDA(escrowPDA, workerAgentPDA);
+      const gates = { hireMode: 1, minReputation: 10000, requireVerified: false, minJobs: 0, hireWindowHours: 0, revealWindowHours: 0 };
+
+      await program.methods
+        .createJob("gated-job", createJobHash("Gated", "Experts only"), new anchor.BN(0.02 * LAMPORTS_PER_SOL), 24, 24, new anchor.BN(0), gates)
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          requesterAgent: requesterAgentPDA,
+          requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1])
+        .rpc();
+
+      try {
+        await program.methods
+          .applyToJob()
+          .accounts({
+            application: applicationPDA,
+            escrow: escrowPDA,
+            workerAgent: workerAgentPDA,
+            worker: creator2.publicKey,
+            session: null,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([creator2])
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("HireGatesNotMet");
+        console.log("Correctly rejected applicant below the reputation gate");
+      }
+    });
+
+    it("hires the best applicant once the hire window closes", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, "auto-hire-job");
+      const [applicationPDA] = getApplicationPDA(escrowPDA, workerAgentPDA);
+      const gates = { hireMode: 2, minReputation: 0, requireVerified: false, minJobs: 1, hireWindowHours: 0, revealWindowHours: 0 };
+
+      await program.methods
+        .createJob("auto-hire-job", createJobHash("Auto", "Best applicant"), new anchor.BN(0.02 * LAMPORTS_PER_SOL), 24, 24, new anchor.BN(0), gates)
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          requesterAgent: requesterAgentPDA,
+          requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1])
+        .rpc();
+
+      await program.methods
+        .applyToJob()
+        .accounts({
+          application: applicationPDA,
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          worker: creator2.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator2])
+        .rpc();
+
+      await program.methods
+        .autoHire()
+        .accounts({
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          application: applicationPDA,
+          requesterAgent: requesterAgentPDA,
+          config: configPDA,
+          pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
+          caller: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .remainingAccounts([
+          { pubkey: applicationPDA, isSigner: false, isWritable: false },
+          { pubkey: workerAgentPDA, isSigner: false, isWritable: false },
+        ])
+        .rpc();
+
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.status).to.equal(1); // STATUS_IN_PROGRESS
+      expect(escrow.worker.toString()).to.equal(workerAgentPDA.toString());
+      expect(escrow.applicationCount).to.equal(1);
+
+      console.log(`Job 'auto-hire-job' auto-hired '${agent2Name}'`);
+    });
+
+    it("requester rejects an application to free an applicant slot", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, "reject-app-job");
+      const [applicationPDA] = getApplicationPDA(escrowPDA, workerAgentPDA);
+      const gates = { hireMode: 2, minReputation: 0, requireVerified: false, minJobs: 0, hireWindowHours: 24, revealWindowHours: 0 };
+
+      await program.methods
+        .createJob("reject-app-job", createJobHash("Reject", "Prune applicants"), new anchor.BN(0.02 * LAMPORTS_PER_SOL), 24, 24, new anchor.BN(0), gates)
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          requesterAgent: requesterAgentPDA,
+          requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1])
+        .rpc();
+
+      await program.methods
+        .applyToJob()
+        .accounts({
+          application: applicationPDA,
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          worker: creator2.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator2])
+        .rpc();
+
+      await program.methods
+        .rejectApplication()
+        .accounts({
+          application: applicationPDA,
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          worker: creator2.publicKey,
+          requesterAgent: requesterAgentPDA,
+          session: null,
+          requester: creator1.publicKey,
+        })
+        .signers([creator1])
+        .rpc();
+
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.applicationCount).to.equal(0);
+      expect(await provider.connection.getAccountInfo(applicationPDA)).to.be.null;
+
+      console.log("Rejected application closed and its slot freed");
+    });
+  });
+
+  describe("Sealed-Bid Auctions", () => {
+    const auctionJobId = "auction-job";
+
+    it("accepts sealed bids and refuses plain applications", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, auctionJobId);
+      const [bidPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("bid"), escrowPDA.toBuffer(), workerAgentPDA.toBuffer()],
+        program.programId
+      );
+      const gates = { hireMode: 3, minReputation: 0, requireVerified: false, minJobs: 0, hireWindowHours: 24, revealWindowHours: 24 };
+
+      await program.methods
+        .createJob(auctionJobId, createJobHash("Auction", "Lowest bid wins"), new anchor.BN(0.1 * LAMPORTS_PER_SOL), 24, 24, new anchor.BN(0), gates)
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          requesterAgent: requesterAgentPDA,
+          requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1])
+        .rpc();
+
+      try {
+        await program.methods
+          .applyToJob()
+          .accounts({
+            application: getApplicationPDA(escrowPDA, workerAgentPDA)[0],
+            escrow: escrowPDA,
+            workerAgent: workerAgentPDA,
+            worker: creator2.publicKey,
+            session: null,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([creator2])
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("AuctionJob");
+      }
+
+      const bidAmount = new anchor.BN(0.06 * LAMPORTS_PER_SOL);
+      const salt = crypto.randomBytes(32);
+      const commitment = Array.from(
+        crypto.createHash("sha256").update(Buffer.concat([bidAmount.toArrayLike(Buffer, "le", 8), salt])).digest()
+      );
+
+      await program.methods
+        .commitBid(commitment)
+        .accounts({
+          bid: bidPDA,
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          requesterAgent: requesterAgentPDA,
+          session: null,
+          worker: creator2.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator2])
+        .rpc();
+
+      const bid = await program.account.bid.fetch(bidPDA);
+      expect(bid.commitment).to.deep.equal(commitment);
+      expect(bid.revealed).to.be.false;
+
+      // Unrevealed bids are tracked so settlement can forfeit their bonds
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.applicationCount).to.equal(1);
+
+      console.log(`Sealed bid committed on '${auctionJobId}'`);
+    });
+  });
+
+  describe("Service Listings", () => {
+    const serviceId = "price-feed";
+
+    it("sells a listed service as an in-progress job", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [listingPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("service"), workerAgentPDA.toBuffer(), Buffer.from(serviceId)],
+        program.programId
+      );
+      const price = new anchor.BN(0.03 * LAMPORTS_PER_SOL);
+      const terms = {
+        price,
+        capability: new anchor.BN(0b001),
+        termsHash: createJobHash("Price feed", "Hourly SOL/USDC report"),
+        timeoutHours: 24,
+        reviewHours: 24,
+        maxQueue: 1,
+      };
+
+      await program.methods
+        .createServiceListing(serviceId, terms)
+        .accounts({
+          listing: listingPDA,
+          agent: workerAgentPDA,
+          config: configPDA,
+          authority: creator2.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator2])
+        .rpc();
+
+      const purchaseAccounts = (escrowPDA: PublicKey) => ({
+        requesterAgent: requesterAgentPDA,
+        escrow: escrowPDA,
+        listing: listingPDA,
+        workerAgent: workerAgentPDA,
+        config: configPDA,
+        pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
+        session: null,
+        requester: creator1.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      });
+
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, "service-job-1");
+      await program.methods
+        .purchaseService("service-job-1", price)
+        .accounts(purchaseAccounts(escrowPDA))
+        .signers([creator1])
+        .rpc();
+
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.status).to.equal(1); // STATUS_IN_PROGRESS
+      expect(escrow.worker.toString()).to.equal(workerAgentPDA.toString());
+      expect(escrow.amount.toNumber()).to.equal(price.toNumber());
+      expect(escrow.service.toString()).to.equal(listingPDA.toString());
+
+      const listing = await program.account.serviceListing.fetch(listingPDA);
+      expect(listing.queueLength).to.equal(1);
+      expect(listing.totalSales).to.equal(1);
+
+      try {
+        const [secondEscrowPDA] = getEscrowPDA(requesterAgentPDA, "service-job-2");
+        await program.methods
+          .purchaseService("service-job-2", price)
+          .accounts(purchaseAccounts(secondEscrowPDA))
+          .signers([creator1])
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ServiceQueueFull");
+      }
+
+      console.log(`Service '${serviceId}' purchased, queue is full`);
+    });
+
+    it("refuses to close a listing while purchased jobs hold slots", async () => {
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [listingPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("service"), workerAgentPDA.toBuffer(), Buffer.from(serviceId)],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .closeService()
+          .accounts({
+            listing: listingPDA,
+            agent: workerAgentPDA,
+            authority: creator2.publicKey,
+          })
+          .signers([creator2])
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ServiceQueueNotEmpty");
+      }
+    });
+  });
+
+  describe("Subcontracting", () => {
+    it("carves a sub-job from the parent and blocks completion until it settles", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [parentPDA] = getEscrowPDA(requesterAgentPDA, "bond-test-job");
+      const [subjobPDA] = getEscrowPDA(workerAgentPDA, "bond-subjob");
+      const subjobAmount = 0.01 * LAMPORTS_PER_SOL;
+
+      const parentBefore = await program.account.escrowAccount.fetch(parentPDA);
+
+      await program.methods
+        .createSubjob("bond-subjob", createJobHash("Sub-task", "Data cleaning"), new anchor.BN(subjobAmount), 24, 24)
+        .accounts({
+          parentEscrow: parentPDA,
+          workerAgent: workerAgentPDA,
+          parentRequesterAgent: requesterAgentPDA,
+          parentRequester: creator1.publicKey,
+          escrow: subjobPDA,
+          config: configPDA,
+          session: null,
+          worker: creator2.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator2, creator1])
+        .rpc();
+
+      let parent = await program.account.escrowAccount.fetch(parentPDA);
+      expect(parent.amount.toNumber()).to.equal(parentBefore.amount.toNumber() - subjobAmount);
+      expect(parent.openSubjobs).to.equal(1);
+
+      const subjob = await program.account.escrowAccount.fetch(subjobPDA);
+      expect(subjob.parent.toString()).to.equal(parentPDA.toString());
+      expect(subjob.requester.toString()).to.equal(workerAgentPDA.toString());
+      expect(subjob.deadlineCap.toNumber()).to.equal(parent.deadline.toNumber());
+
+      try {
+        await program.methods
+          .completeJob(createJobHash("Result", "Parent"), null, null)
+          .accounts({
+            escrow: parentPDA,
+            workerAgent: workerAgentPDA,
+            worker: creator2.publicKey,
+            session: null,
+          })
+          .signers([creator2])
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("SubjobsOpen");
+      }
+
+      // Cancelling the sub-job pays nothing to the worker; settling returns the
+      // refund to the parent escrow
+      const workerBalanceBefore = await provider.connection.getBalance(workerAgentPDA);
+      await program.methods
+        .cancelJob()
+        .accounts({
+          escrow: subjobPDA,
+          requesterAgent: workerAgentPDA,
+          requester: creator2.publicKey,
+          session: null,
+        })
+        .signers([creator2])
+        .rpc();
+      expect(await provider.connection.getBalance(workerAgentPDA)).to.equal(workerBalanceBefore);
+
+      await program.methods
+        .settleSubjob()
+        .accounts({
+          parentEscrow: parentPDA,
+          escrow: subjobPDA,
+          requesterAgent: workerAgentPDA,
+          workerAgent: null,
+          caller: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      parent = await program.account.escrowAccount.fetch(parentPDA);
+      expect(parent.openSubjobs).to.equal(0);
+      expect(parent.amount.toNumber()).to.equal(parentBefore.amount.toNumber());
+      expect(parent.subjobTotal.toNumber()).to.equal(0);
+
+      console.log("Sub-job created, cancelled and settled with its parent");
+    });
+  });
+
+  describe("Pipelines", () => {
+    it("launches stages that wait on their predecessor and refunds the rest on cancel", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [pipelinePDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("pipeline"), requesterAgentPDA.toBuffer(), Buffer.from("etl")],
+        program.programId
+      );
+      const stageAmount = 0.01 * LAMPORTS_PER_SOL;
+      const stages = ["Extract", "Transform", "Load"].map((name) => ({
+        amount: new anchor.BN(stageAmount),
+        jobHash: createJobHash(name, "ETL stage"),
+      }));
+
+      await program.methods
+        .createPipeline("etl", stages)
+        .accounts({
+          requesterAgent: requesterAgentPDA,
+          pipeline: pipelinePDA,
+          config: configPDA,
+          requester: creator1.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1])
+        .rpc();
+
+      const stagePDAs = ["etl-extract", "etl-transform"].map((id) => getEscrowPDA(requesterAgentPDA, id)[0]);
+      for (const [index, id] of ["etl-extract", "etl-transform"].entries()) {
+        await program.methods
+          .launchStage(index, id, 24, 24)
+          .accounts({
+            requesterAgent: requesterAgentPDA,
+            pipeline: pipelinePDA,
+            escrow: stagePDAs[index],
+            config: configPDA,
+            requester: creator1.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([creator1])
+          .rpc();
+      }
+
+      const transform = await program.account.escrowAccount.fetch(stagePDAs[1]);
+      expect(transform.prerequisites.map((key) => key.toString())).to.deep.equal([stagePDAs[0].toString()]);
+      expect(transform.pipeline.toString()).to.equal(pipelinePDA.toString());
+
+      // The second stage cannot be hired before the first completes
+      try {
+        await program.methods
+          .hireAgent()
+          .accounts({
+            config: configPDA,
+            escrow: stagePDAs[1],
+            workerAgent: workerAgentPDA,
+            application: null,
+            worker: null,
+            requesterAgent: requesterAgentPDA,
+            pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
+            requester: creator1.publicKey,
+            session: null,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([creator1])
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("PrerequisitesIncomplete");
+      }
+
+      // Cancelling returns the unlaunched third stage along with the rent,
+      // and cancels the two open stages passed in
+      const rent = await provider.connection.getMinimumBalanceForRentExemption(
+        (await provider.connection.getAccountInfo(pipelinePDA))!.data.length
+      );
+      expect(await provider.connection.getBalance(pipelinePDA)).to.equal(rent + stageAmount);
+      const spentBefore = (await program.account.agentAccount.fetch(requesterAgentPDA)).totalSpent.toNumber();
+
+      await program.methods
+        .cancelPipeline()
+        .accounts({
+          pipeline: pipelinePDA,
+          requesterAgent: requesterAgentPDA,
+          requester: creator1.publicKey,
+        })
+        .remainingAccounts(stagePDAs.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+        .signers([creator1])
+        .rpc();
+
+      expect(await provider.connection.getAccountInfo(pipelinePDA)).to.be.null;
+      for (const stagePDA of stagePDAs) {
+        const stage = await program.account.escrowAccount.fetch(stagePDA);
+        expect(stage.status).to.equal(5); // STATUS_CANCELLED
+        expect(stage.amount.toNumber()).to.equal(0);
+      }
+      const spentAfter = (await program.account.agentAccount.fetch(requesterAgentPDA)).totalSpent.toNumber();
+      expect(spentBefore - spentAfter).to.equal(2 * stageAmount);
+
+      console.log("Pipeline stages launched in order and unlaunched budget refunded");
+    });
   });
 
   describe("Configure Split", () => {
@@ -342,6 +1822,7 @@ describe("agentlink", () => {
       await program.methods
         .configureSplit(2000) // 20%
         .accounts({
+          config: configPDA,
           agent: agentPDA,
           authority: creator1.publicKey,
         })
@@ -361,6 +1842,7 @@ describe("agentlink", () => {
         await program.methods
           .configureSplit(6000) // 60% - above max of 50%
           .accounts({
+            config: configPDA,
             agent: agentPDA,
             authority: creator1.publicKey,
           })
@@ -380,17 +1862,19 @@ describe("agentlink", () => {
     it("creates and hires for dispute test", async () => {
       const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
       const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
-      const [escrowPDA] = getEscrowPDA(disputeJobId);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, disputeJobId);
 
       const jobHash = createJobHash("Dispute Test", "This job will be disputed");
 
       // Create job
       await program.methods
-        .createJob(disputeJobId, jobHash, new anchor.BN(0.1 * LAMPORTS_PER_SOL), 24)
+        .createJob(disputeJobId, jobHash, new anchor.BN(0.1 * LAMPORTS_PER_SOL), 24, 24, new anchor.BN(0), manualHire)
         .accounts({
+          config: configPDA,
           escrow: escrowPDA,
           requesterAgent: requesterAgentPDA,
           requester: creator1.publicKey,
+          session: null,
           systemProgram: anchor.web3.SystemProgram.programId,
         })
         .signers([creator1])
@@ -400,12 +1884,18 @@ describe("agentlink", () => {
       await program.methods
         .hireAgent()
         .accounts({
+          config: configPDA,
           escrow: escrowPDA,
           workerAgent: workerAgentPDA,
+          application: null,
+          worker: creator2.publicKey, // worker co-signs instead of applying
           requesterAgent: requesterAgentPDA,
+          pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
           requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
         })
-        .signers([creator1])
+        .signers([creator1, creator2])
         .rpc();
 
       console.log(`Created and hired for dispute test job`);
@@ -413,7 +1903,8 @@ describe("agentlink", () => {
 
     it("worker can dispute a job", async () => {
       const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
-      const [escrowPDA] = getEscrowPDA(disputeJobId);
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, disputeJobId);
 
       await program.methods
         .disputeJob()
@@ -421,6 +1912,7 @@ describe("agentlink", () => {
           escrow: escrowPDA,
           callerAgent: workerAgentPDA,
           caller: creator2.publicKey,
+          session: null,
         })
         .signers([creator2])
         .rpc();
@@ -432,6 +1924,456 @@ describe("agentlink", () => {
     });
   });
 
+  describe("Dispute Resolution", () => {
+    const disputeJobId = "dispute-test-job";
+
+    it("registers an arbiter", async () => {
+      const [arbiterPDA] = getArbiterPDA(arbiterWallet.publicKey);
+
+      await program.methods
+        .registerArbiter()
+        .accounts({
+          arbiter: arbiterPDA,
+          arbiterAuthority: arbiterWallet.publicKey,
+          config: configPDA,
+          admin: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      const arbiter = await program.account.arbiter.fetch(arbiterPDA);
+      expect(arbiter.authority.toString()).to.equal(arbiterWallet.publicKey.toString());
+      expect(arbiter.active).to.be.true;
+
+      console.log(`Arbiter ${arbiterWallet.publicKey.toString()} registered`);
+    });
+
+    it("admin withdraws collected protocol fees", async () => {
+      const treasuryBalanceBefore = await provider.connection.getBalance(treasuryPDA);
+
+      await program.methods
+        .withdrawTreasury(new anchor.BN(0)) // 0 = withdraw all
+        .accounts({
+          config: configPDA,
+          treasury: treasuryPDA,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      const treasuryBalance = await provider.connection.getBalance(treasuryPDA);
+      expect(treasuryBalance).to.be.lessThan(treasuryBalanceBefore);
+
+      console.log(`Withdrew ${(treasuryBalanceBefore - treasuryBalance) / LAMPORTS_PER_SOL} SOL of protocol fees`);
+    });
+
+    it("assigns the arbiter and resolves the dispute", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, disputeJobId);
+      const [arbiterPDA] = getArbiterPDA(arbiterWallet.publicKey);
+
+      await program.methods
+        .assignArbiter()
+        .accounts({
+          escrow: escrowPDA,
+          arbiter: arbiterPDA,
+          config: configPDA,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      const requesterAgentBefore = await program.account.agentAccount.fetch(requesterAgentPDA);
+
+      await program.methods
+        .resolveDispute(5000) // 50/50 split
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          workerCreator: creator2.publicKey,
+          requesterAgent: requesterAgentPDA,
+          pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
+          requester: creator1.publicKey,
+          arbiter: arbiterPDA,
+          arbiterAuthority: arbiterWallet.publicKey,
+        })
+        .signers([arbiterWallet])
+        .rpc();
+
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.status).to.equal(6); // STATUS_RESOLVED
+
+      const requesterAgent = await program.account.agentAccount.fetch(requesterAgentPDA);
+      expect(requesterAgent.totalSpent.toNumber()).to.be.lessThan(requesterAgentBefore.totalSpent.toNumber());
+
+      const arbiter = await program.account.arbiter.fetch(arbiterPDA);
+      expect(arbiter.disputesResolved).to.equal(1);
+
+      console.log(`Job '${disputeJobId}' resolved by arbiter`);
+    });
+  });
+
+  describe("Token Escrow", () => {
+    const tokenJobId = "token-test-job";
+    let mint: PublicKey;
+    let requesterToken: PublicKey;
+
+    before(async () => {
+      mint = await createMint(provider.connection, creator1, creator1.publicKey, null, 6);
+      requesterToken = await createAccount(provider.connection, creator1, mint, creator1.publicKey);
+      await mintTo(provider.connection, creator1, mint, requesterToken, creator1, 1_000_000);
+    });
+
+    it("creates a token job with escrow vault", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, tokenJobId);
+      const [vaultPDA] = getVaultPDA(escrowPDA);
+
+      const jobHash = createJobHash("Token Job", "Paid in tokens");
+
+      await program.methods
+        .createJobToken(tokenJobId, jobHash, new anchor.BN(250_000), 24, 24)
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          vault: vaultPDA,
+          mint,
+          requesterAgent: requesterAgentPDA,
+          requesterToken,
+          requester: creator1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1])
+        .rpc();
+
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.mint.toString()).to.equal(mint.toString());
+      expect(escrow.amount.toNumber()).to.equal(250_000);
+
+      const vault = await getAccount(provider.connection, vaultPDA);
+      expect(Number(vault.amount)).to.equal(250_000);
+
+      console.log(`Token job '${tokenJobId}' created`);
+    });
+
+    it("cancels token job and refunds the vault", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, tokenJobId);
+      const [vaultPDA] = getVaultPDA(escrowPDA);
+
+      await program.methods
+        .cancelJobToken()
+        .accounts({
+          escrow: escrowPDA,
+          vault: vaultPDA,
+          mint,
+          requesterAgent: requesterAgentPDA,
+          requesterToken,
+          requester: creator1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([creator1])
+        .rpc();
+
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.status).to.equal(5); // STATUS_CANCELLED
+
+      const refunded = await getAccount(provider.connection, requesterToken);
+      expect(Number(refunded.amount)).to.equal(1_000_000);
+
+      console.log(`Token job '${tokenJobId}' cancelled and refunded`);
+    });
+
+    it("rejects native approval of a token escrow", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, tokenJobId);
+
+      try {
+        await program.methods
+          .approveJob()
+          .accounts({
+            config: configPDA,
+            treasury: treasuryPDA,
+            escrow: escrowPDA,
+            workerAgent: workerAgentPDA,
+            workerCreator: creator2.publicKey,
+            requesterAgent: requesterAgentPDA,
+            pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
+            requester: creator1.publicKey,
+            session: null,
+          })
+          .signers([creator1])
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (err: any) {
+        expect(err.message).to.include("TokenEscrow");
+      }
+    });
+
+    it("closes the token escrow and its vault", async () => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, tokenJobId);
+      const [vaultPDA] = getVaultPDA(escrowPDA);
+
+      await program.methods
+        .closeEscrowToken()
+        .accounts({
+          escrow: escrowPDA,
+          vault: vaultPDA,
+          mint,
+          payer: creator1.publicKey,
+          caller: creator1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([creator1])
+        .rpc();
+
+      expect(await provider.connection.getAccountInfo(vaultPDA)).to.be.null;
+      expect(await provider.connection.getAccountInfo(escrowPDA)).to.be.null;
+    });
+
+    // Create a token job, hire the second agent by co-signature and submit its work
+    const createHiredTokenJob = async (
+      id: string,
+      tokenMint: PublicKey,
+      fromToken: PublicKey,
+      amount: number,
+      tokenProgram: PublicKey
+    ) => {
+      const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const [escrowPDA] = getEscrowPDA(requesterAgentPDA, id);
+      const [vaultPDA] = getVaultPDA(escrowPDA);
+
+      await program.methods
+        .createJobToken(id, createJobHash("Token Job", id), new anchor.BN(amount), 24, 24)
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          vault: vaultPDA,
+          mint: tokenMint,
+          requesterAgent: requesterAgentPDA,
+          requesterToken: fromToken,
+          requester: creator1.publicKey,
+          tokenProgram,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1])
+        .rpc();
+
+      await program.methods
+        .hireAgent()
+        .accounts({
+          config: configPDA,
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          application: null,
+          worker: creator2.publicKey,
+          requesterAgent: requesterAgentPDA,
+          pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
+          requester: creator1.publicKey,
+          session: null,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator1, creator2])
+        .rpc();
+
+      await program.methods
+        .completeJob(createJobHash("Result", id), "ipfs://bafy-token-result", null)
+        .accounts({
+          escrow: escrowPDA,
+          workerAgent: workerAgentPDA,
+          worker: creator2.publicKey,
+          session: null,
+        })
+        .signers([creator2])
+        .rpc();
+
+      return { requesterAgentPDA, workerAgentPDA, escrowPDA, vaultPDA };
+    };
+
+    // Token accounts for the worker agent, its creator and the treasury
+    const createPayoutAccounts = async (tokenMint: PublicKey, tokenProgram: PublicKey) => {
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const create = (owner: PublicKey) =>
+        createAccount(provider.connection, creator1, tokenMint, owner, Keypair.generate(), undefined, tokenProgram);
+      return {
+        workerToken: await create(workerAgentPDA),
+        workerCreatorToken: await create(creator2.publicKey),
+        treasuryToken: await create(treasuryPDA),
+      };
+    };
+
+    // Expected protocol fee, creator split and worker share of an escrowed amount
+    const expectedPayout = async (amount: number) => {
+      const config = await program.account.protocolConfig.fetch(configPDA);
+      const workerAgent = await program.account.agentAccount.fetch(getAgentPDA(creator2.publicKey, agent2Name)[0]);
+      const protocolFee = Math.floor((amount * config.protocolFeeBps) / 10000);
+      const creatorAmount = Math.floor(((amount - protocolFee) * workerAgent.creatorSplitBps) / 10000);
+      return { protocolFee, creatorAmount, workerAmount: amount - protocolFee - creatorAmount };
+    };
+
+    it("pays the worker, its creator and the treasury on approval", async () => {
+      const { requesterAgentPDA, workerAgentPDA, escrowPDA, vaultPDA } = await createHiredTokenJob(
+        "token-approve-job",
+        mint,
+        requesterToken,
+        200_000,
+        TOKEN_PROGRAM_ID
+      );
+      const { workerToken, workerCreatorToken, treasuryToken } = await createPayoutAccounts(mint, TOKEN_PROGRAM_ID);
+      const expected = await expectedPayout(200_000);
+
+      await program.methods
+        .approveJobToken()
+        .accounts({
+          escrow: escrowPDA,
+          vault: vaultPDA,
+          mint,
+          workerAgent: workerAgentPDA,
+          workerToken,
+          workerCreatorToken,
+          config: configPDA,
+          treasury: treasuryPDA,
+          treasuryToken,
+          pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
+          requesterAgent: requesterAgentPDA,
+          requester: creator1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([creator1])
+        .rpc();
+
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.status).to.equal(3); // STATUS_COMPLETED
+
+      expect(Number((await getAccount(provider.connection, workerToken)).amount)).to.equal(expected.workerAmount);
+      expect(Number((await getAccount(provider.connection, workerCreatorToken)).amount)).to.equal(expected.creatorAmount);
+      expect(Number((await getAccount(provider.connection, treasuryToken)).amount)).to.equal(expected.protocolFee);
+      expect(Number((await getAccount(provider.connection, vaultPDA)).amount)).to.equal(0);
+
+      console.log("Token job 'token-approve-job' approved and paid out");
+    });
+
+    it("escrows and pays out net of Token-2022 transfer fees", async () => {
+      // Mint withholding 1% of every transfer
+      const mintKeypair = Keypair.generate();
+      const mintLen = getMintLen([ExtensionType.TransferFeeConfig]);
+      await sendAndConfirmTransaction(
+        provider.connection,
+        new Transaction().add(
+          SystemProgram.createAccount({
+            fromPubkey: creator1.publicKey,
+            newAccountPubkey: mintKeypair.publicKey,
+            space: mintLen,
+            lamports: await provider.connection.getMinimumBalanceForRentExemption(mintLen),
+            programId: TOKEN_2022_PROGRAM_ID,
+          }),
+          createInitializeTransferFeeConfigInstruction(
+            mintKeypair.publicKey,
+            creator1.publicKey,
+            creator1.publicKey,
+            100,
+            BigInt(1_000_000_000),
+            TOKEN_2022_PROGRAM_ID
+          ),
+          createInitializeMintInstruction(mintKeypair.publicKey, 6, creator1.publicKey, null, TOKEN_2022_PROGRAM_ID)
+        ),
+        [creator1, mintKeypair]
+      );
+      const feeMint = mintKeypair.publicKey;
+      const feeConfig = getTransferFeeConfig(
+        await getMint(provider.connection, feeMint, undefined, TOKEN_2022_PROGRAM_ID)
+      )!;
+      const epoch = BigInt((await provider.connection.getEpochInfo()).epoch);
+      const received = (amount: number) => amount - Number(calculateEpochFee(feeConfig, epoch, BigInt(amount)));
+
+      const feeRequesterToken = await createAccount(
+        provider.connection,
+        creator1,
+        feeMint,
+        creator1.publicKey,
+        Keypair.generate(),
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+      await mintTo(provider.connection, creator1, feeMint, feeRequesterToken, creator1, 1_000_000, [], undefined, TOKEN_2022_PROGRAM_ID);
+
+      const { requesterAgentPDA, workerAgentPDA, escrowPDA, vaultPDA } = await createHiredTokenJob(
+        "token-fee-job",
+        feeMint,
+        feeRequesterToken,
+        1_000_000,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      // The escrow holds only what the vault received after the transfer fee
+      const escrowed = received(1_000_000);
+      const vault = await getAccount(provider.connection, vaultPDA, undefined, TOKEN_2022_PROGRAM_ID);
+      expect(Number(vault.amount)).to.equal(escrowed);
+      const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+      expect(escrow.amount.toNumber()).to.equal(escrowed);
+
+      const { workerToken, workerCreatorToken, treasuryToken } = await createPayoutAccounts(feeMint, TOKEN_2022_PROGRAM_ID);
+      const expected = await expectedPayout(escrowed);
+
+      await program.methods
+        .approveJobToken()
+        .accounts({
+          escrow: escrowPDA,
+          vault: vaultPDA,
+          mint: feeMint,
+          workerAgent: workerAgentPDA,
+          workerToken,
+          workerCreatorToken,
+          config: configPDA,
+          treasury: treasuryPDA,
+          treasuryToken,
+          pair: getPairPDA(requesterAgentPDA, workerAgentPDA)[0],
+          requesterAgent: requesterAgentPDA,
+          requester: creator1.publicKey,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([creator1])
+        .rpc();
+
+      // Each payout leg is itself charged the transfer fee on receipt
+      const balance = async (account: PublicKey) =>
+        Number((await getAccount(provider.connection, account, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+      expect(await balance(workerToken)).to.equal(received(expected.workerAmount));
+      expect(await balance(workerCreatorToken)).to.equal(received(expected.creatorAmount));
+      expect(await balance(treasuryToken)).to.equal(received(expected.protocolFee));
+      expect(await balance(vaultPDA)).to.equal(0);
+
+      console.log(`Token-2022 job escrowed ${escrowed} of 1000000 after transfer fees`);
+    });
+  });
+
+  describe("Reputation", () => {
+    it("anyone can refresh an agent's reputation", async () => {
+      const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+      const workerAgentBefore = await program.account.agentAccount.fetch(workerAgentPDA);
+
+      await program.methods
+        .refreshReputation()
+        .accounts({
+          agent: workerAgentPDA,
+        })
+        .rpc();
+
+      // Last job completed moments ago, so no decay applies yet
+      const workerAgent = await program.account.agentAccount.fetch(workerAgentPDA);
+      expect(workerAgent.lastCompletedAt.toNumber()).to.be.greaterThan(0);
+      expect(workerAgent.reputationScore).to.equal(workerAgentBefore.reputationScore);
+      expect(workerAgent.disputesLost).to.equal(0);
+
+      console.log(`Agent '${agent2Name}' reputation: ${workerAgent.reputationScore}`);
+    });
+  });
+
   describe("Summary", () => {
     it("prints final agent states", async () => {
       const [agent1PDA] = getAgentPDA(creator1.publicKey, agent1Name);
@@ -0,0 +1,250 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { BankrunProvider } from "anchor-bankrun";
+import { Clock, ProgramTestContext, startAnchor } from "solana-bankrun";
+import { PublicKey, Keypair, LAMPORTS_PER_SOL, SystemProgram, Transaction } from "@solana/web3.js";
+import {
+  AccountLayout,
+  MINT_SIZE,
+  ACCOUNT_SIZE,
+  TOKEN_PROGRAM_ID,
+  createInitializeAccount3Instruction,
+  createInitializeMint2Instruction,
+  createMintToInstruction,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { Agentlink } from "../target/types/agentlink";
+import IDL from "../target/idl/agentlink.json";
+
+// The review window can only lapse by moving the clock forward, which the
+// local validator cannot do, so this suite runs the program in bankrun.
+describe("agentlink token timeout", () => {
+  const creator1 = Keypair.generate();
+  const creator2 = Keypair.generate();
+  const agent1Name = "matrix";
+  const agent2Name = "oracle";
+  const jobId = "token-timeout-job";
+  const programId = new PublicKey(IDL.address);
+
+  let context: ProgramTestContext;
+  let provider: BankrunProvider;
+  let program: Program<Agentlink>;
+
+  const getAgentPDA = (creator: PublicKey, name: string) =>
+    PublicKey.findProgramAddressSync([Buffer.from("agent"), creator.toBuffer(), Buffer.from(name)], programId);
+  const getEscrowPDA = (requesterAgent: PublicKey, id: string) =>
+    PublicKey.findProgramAddressSync([Buffer.from("escrow"), requesterAgent.toBuffer(), Buffer.from(id)], programId);
+  const getVaultPDA = (escrow: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("vault"), escrow.toBuffer()], programId);
+  const getPairPDA = (requesterAgent: PublicKey, workerAgent: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("pair"), requesterAgent.toBuffer(), workerAgent.toBuffer()], programId);
+  const [configPDA, configBump] = PublicKey.findProgramAddressSync([Buffer.from("config")], programId);
+  const [treasuryPDA, treasuryBump] = PublicKey.findProgramAddressSync([Buffer.from("treasury")], programId);
+
+  const protocolFeeBps = 100;
+  const defaultSplitBps = 1000;
+
+  // Program-owned account preloaded into the bank
+  const programAccount = (address: PublicKey, data: Buffer) => ({
+    address,
+    info: { lamports: LAMPORTS_PER_SOL, data, owner: programId, executable: false },
+  });
+  const walletAccount = (address: PublicKey) => ({
+    address,
+    info: { lamports: 10 * LAMPORTS_PER_SOL, data: Buffer.alloc(0), owner: SystemProgram.programId, executable: false },
+  });
+
+  const tokenBalance = async (account: PublicKey) =>
+    Number(AccountLayout.decode((await context.banksClient.getAccount(account))!.data).amount);
+
+  // Create a token account owned by `owner` (which may be a PDA)
+  const createTokenAccount = async (mint: PublicKey, owner: PublicKey) => {
+    const account = Keypair.generate();
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: provider.wallet.publicKey,
+          newAccountPubkey: account.publicKey,
+          space: ACCOUNT_SIZE,
+          lamports: LAMPORTS_PER_SOL / 100,
+          programId: TOKEN_PROGRAM_ID,
+        }),
+        createInitializeAccount3Instruction(account.publicKey, mint, owner)
+      ),
+      [account]
+    );
+    return account.publicKey;
+  };
+
+  before(async () => {
+    // initialize_config needs an upgradeable program, so the config and
+    // treasury are preloaded instead
+    const coder = new anchor.BorshAccountsCoder(IDL as anchor.Idl);
+    const config = await coder.encode("ProtocolConfig", {
+      bump: configBump,
+      admin: creator1.publicKey,
+      treasury: treasuryPDA,
+      maxSplitBps: 5000,
+      defaultSplitBps,
+      verificationThreshold: 3,
+      allowedTimeouts: [24, 48, 72, 0],
+      protocolFeeBps,
+      paused: false,
+      minReputationJobValue: new anchor.BN(0),
+      reputationMints: [],
+    });
+    const treasury = await coder.encode("Treasury", { bump: treasuryBump });
+
+    context = await startAnchor(
+      ".",
+      [],
+      [
+        programAccount(configPDA, config),
+        programAccount(treasuryPDA, treasury),
+        walletAccount(creator1.publicKey),
+        walletAccount(creator2.publicKey),
+      ]
+    );
+    provider = new BankrunProvider(context);
+    program = new Program<Agentlink>(IDL as Agentlink, provider);
+
+    for (const [creator, name] of [
+      [creator1, agent1Name],
+      [creator2, agent2Name],
+    ] as [Keypair, string][]) {
+      await program.methods
+        .registerAgent(name)
+        .accounts({
+          config: configPDA,
+          agent: getAgentPDA(creator.publicKey, name)[0],
+          creator: creator.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([creator])
+        .rpc();
+    }
+  });
+
+  it("pays a token job out once the review window lapses", async () => {
+    const [requesterAgentPDA] = getAgentPDA(creator1.publicKey, agent1Name);
+    const [workerAgentPDA] = getAgentPDA(creator2.publicKey, agent2Name);
+    const [escrowPDA] = getEscrowPDA(requesterAgentPDA, jobId);
+    const [vaultPDA] = getVaultPDA(escrowPDA);
+    const [pairPDA] = getPairPDA(requesterAgentPDA, workerAgentPDA);
+
+    const mint = Keypair.generate();
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: provider.wallet.publicKey,
+          newAccountPubkey: mint.publicKey,
+          space: MINT_SIZE,
+          lamports: LAMPORTS_PER_SOL / 100,
+          programId: TOKEN_PROGRAM_ID,
+        }),
+        createInitializeMint2Instruction(mint.publicKey, 6, creator1.publicKey, null)
+      ),
+      [mint]
+    );
+    const requesterToken = await createTokenAccount(mint.publicKey, creator1.publicKey);
+    await provider.sendAndConfirm(
+      new Transaction().add(createMintToInstruction(mint.publicKey, requesterToken, creator1.publicKey, 500_000)),
+      [creator1]
+    );
+
+    await program.methods
+      .createJobToken(jobId, Array(32).fill(1), new anchor.BN(500_000), 24, 24)
+      .accounts({
+        config: configPDA,
+        escrow: escrowPDA,
+        vault: vaultPDA,
+        mint: mint.publicKey,
+        requesterAgent: requesterAgentPDA,
+        requesterToken,
+        requester: creator1.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([creator1])
+      .rpc();
+
+    await program.methods
+      .hireAgent()
+      .accounts({
+        config: configPDA,
+        escrow: escrowPDA,
+        workerAgent: workerAgentPDA,
+        application: null,
+        worker: creator2.publicKey,
+        requesterAgent: requesterAgentPDA,
+        pair: pairPDA,
+        requester: creator1.publicKey,
+        session: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([creator1, creator2])
+      .rpc();
+
+    await program.methods
+      .completeJob(Array(32).fill(2), "ipfs://bafy-token-timeout", null)
+      .accounts({
+        escrow: escrowPDA,
+        workerAgent: workerAgentPDA,
+        worker: creator2.publicKey,
+        session: null,
+      })
+      .signers([creator2])
+      .rpc();
+
+    const workerToken = await createTokenAccount(mint.publicKey, workerAgentPDA);
+    const workerCreatorToken = await createTokenAccount(mint.publicKey, creator2.publicKey);
+    const treasuryToken = await createTokenAccount(mint.publicKey, treasuryPDA);
+
+    // Move past the 24 hour review window
+    const clock = await context.banksClient.getClock();
+    context.setClock(
+      new Clock(
+        clock.slot,
+        clock.epochStartTimestamp,
+        clock.epoch,
+        clock.leaderScheduleEpoch,
+        clock.unixTimestamp + BigInt(25 * 3600)
+      )
+    );
+
+    await program.methods
+      .claimTimeoutToken()
+      .accounts({
+        escrow: escrowPDA,
+        vault: vaultPDA,
+        mint: mint.publicKey,
+        workerAgent: workerAgentPDA,
+        workerToken,
+        workerCreatorToken,
+        config: configPDA,
+        treasury: treasuryPDA,
+        treasuryToken,
+        pair: pairPDA,
+        requesterAgent: requesterAgentPDA,
+        caller: provider.wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const protocolFee = Math.floor((500_000 * protocolFeeBps) / 10000);
+    const creatorAmount = Math.floor(((500_000 - protocolFee) * defaultSplitBps) / 10000);
+    const workerAmount = 500_000 - protocolFee - creatorAmount;
+
+    const escrow = await program.account.escrowAccount.fetch(escrowPDA);
+    expect(escrow.status).to.equal(3); // STATUS_COMPLETED
+    expect(await tokenBalance(workerToken)).to.equal(workerAmount);
+    expect(await tokenBalance(workerCreatorToken)).to.equal(creatorAmount);
+    expect(await tokenBalance(treasuryToken)).to.equal(protocolFee);
+    expect(await tokenBalance(vaultPDA)).to.equal(0);
+
+    const requesterAgent = await program.account.agentAccount.fetch(requesterAgentPDA);
+    expect(requesterAgent.timeoutsSuffered).to.equal(1);
+
+    console.log(`Token job '${jobId}' paid out by timeout`);
+  });
+});